@@ -0,0 +1,72 @@
+use std::{io, net::{IpAddr, SocketAddr, ToSocketAddrs}, sync::mpsc::{self, Receiver, TryRecvError}, thread};
+
+pub const DEFAULT_ECON_PORT: u16 = 8303;
+pub const MAX_RESOLVED: usize = 8;
+
+// accepts "1.2.3.4:8303", "[::1]:8303", "::1", "localhost" and "play.example.org:8303"
+pub fn resolve(address: &str) -> io::Result<Vec<SocketAddr>> {
+    let address = address.trim();
+
+    if let Ok(address) = address.parse::<SocketAddr>() {
+        return Ok(vec![address]);
+    }
+
+    let bare = address.trim_start_matches('[').trim_end_matches(']');
+
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, DEFAULT_ECON_PORT)]);
+    }
+
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid port"))?;
+            (host, port)
+        },
+        None => (address, DEFAULT_ECON_PORT)
+    };
+
+    if host.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty host"));
+    }
+
+    let mut addresses = Vec::new();
+
+    for address in (host, port).to_socket_addrs()? {
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+
+    addresses.truncate(MAX_RESOLVED);
+
+    Ok(addresses)
+}
+
+// `resolve` on a background thread, a slow DNS server must not freeze the UI
+pub struct Lookup {
+    receiver: Receiver<io::Result<Vec<SocketAddr>>>
+}
+
+impl Lookup {
+    pub fn start(address: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let address = address.to_string();
+
+        thread::spawn(move || {
+            let _ = sender.send(resolve(&address));
+        });
+
+        Self { receiver }
+    }
+
+    // None while the lookup is still running
+    pub fn poll(&self) -> Option<io::Result<Vec<SocketAddr>>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("lookup stopped")))
+        }
+    }
+}
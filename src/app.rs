@@ -1,21 +1,73 @@
-use std::{collections::VecDeque, net::SocketAddr};
+use std::{collections::{BTreeMap, BTreeSet, VecDeque}, io, net::SocketAddr, time::{Duration, Instant}};
 
-use crossterm::event::{KeyEvent, KeyCode};
-use tui::{Frame, backend::Backend, style::{Style, Color, Modifier}, widgets::{Block, Paragraph, Wrap, Tabs, Borders}, layout::{Direction, Rect, Constraint, Layout, Alignment}, text::{Spans, Span}};
-use tw_econ::connection::Connection;
+use crossterm::event::{KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind};
+use tui_input::Input;
+use tui::{Frame, backend::Backend, style::{Style, Color, Modifier}, widgets::{Block, Paragraph, Wrap, Borders, Clear, List, ListItem, ListState, Table, TableState, Row, Cell}, layout::{Direction, Rect, Constraint, Layout, Alignment}, text::{Spans, Span}};
 
-use crate::{state::{Screen, MainElements, Main, AddConnectionElements, AddConnection}, econtab::EconTab, stringarray::StringArray};
+use crate::{address::Lookup, clipboard, colors::Palette, state::{Screen, MainElements, Main, AddConnectionElements, AddConnection, ConnectError, PlayerMenu, PlayerMenuStep, PlayerAction, Bans, BanForm, BanFormKind, BanFormElements, Broadcast, BroadcastResult, Export, SplitDirection}, completion::Completion, econtab::{EconTab, Backoff, ConnectionState}, config::Config, filter::Category, find::{self, Find}, lineedit, parser::{Event, Line}, profile::{Profile, Profiles}, export::{self, ExportScope}, sessionlog::{LogFormat, SessionLog}, timeline::{self, Timeline}};
+
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct App {
+    config: Config,
+    palette: Palette,
     current_screen: Screen,
-    econ_tabs: VecDeque<EconTab>
+    econ_tabs: VecDeque<EconTab>,
+    profiles: Profiles,
+    // why profiles.toml could not be loaded. it is not saved over while this is set
+    profiles_error: Option<String>,
+    pending: Option<PendingConnection>,
+    lookup: Option<PendingLookup>,
+    timeline: Timeline,
+    // one per console pane, in the order of `Main::panes`
+    console_areas: Vec<Rect>
+}
+
+enum MenuOutcome {
+    Open,
+    Close,
+    Send(String)
+}
+
+// AddConnection waiting for the host to resolve
+struct PendingLookup {
+    lookup: Lookup,
+    profile: Profile,
+    save: bool
+}
+
+// a connection from AddConnection waiting for the server to accept the password
+struct PendingConnection {
+    econ_tab: EconTab,
+    profile: Profile,
+    save: bool,
+    started: Instant
 }
 
 impl App {
     pub fn new() -> Self {
+        let config = Config::load();
+        let (profiles, profiles_error) = match Profiles::load() {
+            Ok(profiles) => (profiles, None),
+            Err(error) => (Profiles::default(), Some(error.to_string()))
+        };
+        let econ_tabs = profiles
+            .profiles
+            .iter()
+            .map(|profile| Self::restore(profile, &config))
+            .collect();
+
         Self {
+            palette: Palette::new(&config.colors),
+            config,
             current_screen: Screen::Welcome,
-            econ_tabs: VecDeque::new()
+            econ_tabs,
+            profiles,
+            profiles_error,
+            pending: None,
+            lookup: None,
+            timeline: Timeline::new(),
+            console_areas: Vec::new()
         }
     }
 
@@ -25,61 +77,353 @@ impl App {
                 self.current_screen = Screen::Main(Main::new());
             },
             Screen::Main(data) =>{
+                if let Some(broadcast) = &mut data.broadcast {
+                    if !Self::on_broadcast_key(broadcast, &data.marked, &mut self.econ_tabs, key) {
+                        data.broadcast = None;
+                    }
+                    return false;
+                }
+
+                if data.filtering && data.timeline {
+                    match key.code {
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Tab => data.filtering = false,
+                        _ => if lineedit::handle(&mut self.timeline.filter.query, key) {
+                            self.timeline.filter.enabled = true;
+                            self.timeline.refilter();
+                        }
+                    }
+                    return false;
+                }
+
+                if data.filtering {
+                    if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Enter | KeyCode::Tab => data.filtering = false,
+                            _ => if lineedit::handle(&mut econ_tab.filter.query, key) {
+                                econ_tab.filter.enabled = true;
+                                econ_tab.refilter();
+                            }
+                        }
+                    }
+                    else {
+                        data.filtering = false;
+                    }
+                    return false;
+                }
+
+                if let Some(export) = &mut data.export {
+                    let econ_tab = self.econ_tabs.get(data.connection as _);
+                    match (key.code, &export.result, econ_tab) {
+                        (KeyCode::Esc, _, _) | (KeyCode::Enter, Some(_), _) | (_, _, None) => data.export = None,
+                        (_, Some(_), _) => {},
+                        (KeyCode::Tab, _, Some(econ_tab)) => {
+                            export.scope = export.scope.next();
+                            export.count = econ_tab.export_lines(export.scope).len();
+                        },
+                        (KeyCode::BackTab, _, _) => export.set_format(export.format.next()),
+                        (KeyCode::Enter, None, _) if !export.overwrite && export::expand(export.path.value().trim()).exists() => {
+                            export.overwrite = true;
+                        },
+                        (KeyCode::Enter, None, Some(econ_tab)) => {
+                            let lines = econ_tab.export_lines(export.scope);
+                            let path = export::expand(export.path.value().trim());
+                            let title = format!("{} - {}", econ_tab.name, export.scope.label());
+
+                            export.result = Some(match export::write(&path, export.format, &title, &lines, &self.palette) {
+                                Ok(()) => Ok(format!("Wrote {} lines to {}", lines.len(), path.display())),
+                                Err(error) => Err(format!("Could not write {}: {}", path.display(), error))
+                            });
+                        },
+                        _ => if lineedit::handle(&mut export.path, key) {
+                            export.overwrite = false;
+                        }
+                    }
+                    return false;
+                }
+
+                if data.finding {
+                    match self.econ_tabs.get_mut(data.connection as _) {
+                        Some(econ_tab) => match key.code {
+                            KeyCode::Enter | KeyCode::Tab => data.finding = false,
+                            KeyCode::Esc => {
+                                econ_tab.find = None;
+                                data.finding = false;
+                            },
+                            _ => if econ_tab.find.as_mut().is_some_and(|find| lineedit::handle(&mut find.query, key)) {
+                                econ_tab.refind();
+                            }
+                        },
+                        None => data.finding = false
+                    }
+                    return false;
+                }
+
+                if let Some(menu) = &mut data.menu {
+                    match Self::on_menu_key(menu, key) {
+                        MenuOutcome::Open => {},
+                        MenuOutcome::Close => data.menu = None,
+                        MenuOutcome::Send(command) => {
+                            if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
+                                let _ = econ_tab.send(command);
+                                econ_tab.request_status();
+                            }
+                            data.menu = None;
+                        }
+                    }
+                    return false;
+                }
+
+                let input_tab = self.econ_tabs.get(data.connection as _).filter(|_| data.active == MainElements::Input);
+                let searching = input_tab.is_some_and(|t| t.history.search().is_some());
+                let completing = input_tab.is_some_and(|t| t.completion.is_some());
+                // Tab completes once something is typed, Shift-Tab still leaves the Input
+                let typing = input_tab.is_some_and(|t| !t.buffer.value().is_empty());
+
                 match key.code {
-                    KeyCode::Up => data.active = data.active.prev(),
-                    KeyCode::Down => data.active = data.active.next(),
-                    KeyCode::Esc => self.current_screen = Screen::Exit,
+                    KeyCode::Tab if !completing && !typing => data.active = data.active.next(),
+                    KeyCode::BackTab if !completing => data.active = data.active.prev(),
+                    KeyCode::Esc if !searching && !completing => self.current_screen = Screen::Exit(data.clone()),
 
                     _ => match data.active {
-                        MainElements::Connections => match key.code {
-                            KeyCode::Left => if self.econ_tabs.len() > 0 && data.connection > 0 {
-                                data.connection -= 1;
+                        MainElements::Connections => {
+                            let tree = connection_tree(&self.econ_tabs, &data.collapsed);
+                            let cursor = tree_cursor(&tree, data, &self.econ_tabs);
+                            // a group header stands for every connection in it
+                            let members = match tree.get(cursor) {
+                                Some(TreeItem::Group(group)) => group_members(&self.econ_tabs, group),
+                                Some(TreeItem::Connection(i)) => vec![*i],
+                                Some(TreeItem::All) => (0..self.econ_tabs.len() as u8).collect(),
+                                None => Vec::new()
+                            };
+
+                            match key.code {
+                                KeyCode::Up if !tree.is_empty() => select_tree_item(data, &tree[(cursor + tree.len() - 1) % tree.len()]),
+                                KeyCode::Down if !tree.is_empty() => select_tree_item(data, &tree[(cursor + 1) % tree.len()]),
+                                KeyCode::Left => match tree.get(cursor) {
+                                    Some(TreeItem::Group(group)) => {
+                                        data.collapsed.insert(group.clone());
+                                    },
+                                    Some(TreeItem::Connection(i)) if !self.econ_tabs[*i as usize].group.is_empty() => {
+                                        data.group = Some(self.econ_tabs[*i as usize].group.clone());
+                                    },
+                                    _ => {}
+                                },
+                                KeyCode::Right => if let Some(TreeItem::Group(group)) = tree.get(cursor) {
+                                    data.collapsed.remove(group);
+                                },
+                                KeyCode::Char(' ') if !members.is_empty() && members.iter().all(|i| data.marked.contains(i)) => {
+                                    for i in &members {
+                                        data.marked.remove(i);
+                                    }
+                                },
+                                KeyCode::Char(' ') => data.marked.extend(&members),
+                                KeyCode::Enter if matches!(tree.get(cursor), Some(TreeItem::Group(_))) => {
+                                    data.marked.extend(members);
+                                    data.broadcast = Some(Broadcast::default());
+                                },
+                                KeyCode::Enter if !data.marked.is_empty() => data.broadcast = Some(Broadcast::default()),
+                                KeyCode::Char('c') => for i in members {
+                                    let econ_tab = &mut self.econ_tabs[i as usize];
+                                    let idle = matches!(econ_tab.state, ConnectionState::Disconnected | ConnectionState::AuthFailed | ConnectionState::Reconnecting(_));
+                                    if idle && econ_tab.launch().is_err() {
+                                        econ_tab.state = ConnectionState::Reconnecting(Backoff::new());
+                                    }
+                                },
+                                KeyCode::Char('x') => for i in members {
+                                    self.econ_tabs[i as usize].disconnect();
+                                },
+                                // off, text, json. saved profiles remember the choice
+                                KeyCode::Char('l') => if let Some(TreeItem::Connection(i)) = tree.get(cursor) {
+                                    let econ_tab = &mut self.econ_tabs[*i as usize];
+                                    let format = econ_tab.log.as_ref().map_or(LogFormat::Off, |log| log.format).next();
+                                    econ_tab.log = SessionLog::new(&econ_tab.name, format, self.config.log_max_bytes);
+
+                                    if let Some(profile) = self.profiles.profiles.iter_mut().find(|p| p.name == econ_tab.name) {
+                                        profile.log = format;
+                                        if let Err(error) = Self::save_profiles(&self.profiles, &self.profiles_error) {
+                                            econ_tab.notice(format!("could not save the log setting: {}", error));
+                                        }
+                                    }
+                                },
+                                KeyCode::Char('b') => if let Some(TreeItem::Connection(i)) = tree.get(cursor) {
+                                    let econ_tab = &mut self.econ_tabs[*i as usize];
+                                    econ_tab.request_bans();
+                                    self.current_screen = Screen::Bans(Bans::new(data.clone()));
+                                },
+                                _ => {}
                             }
-                            else {
-                                data.connection = (self.econ_tabs.len() - 1) as _;
-                            },
-                            KeyCode::Right => if self.econ_tabs.len() > 0 && data.connection < (self.econ_tabs.len() - 1) as _ {
-                                data.connection += 1;
+                        },
+                        MainElements::Console if data.timeline => {
+                            let page = self.console_areas.get(data.pane).map_or(1, |area| area.height.saturating_sub(2).max(1)) as usize;
+                            let timeline = &mut self.timeline;
+                            match key.code {
+                                KeyCode::Left | KeyCode::Up => timeline.scroll_up(&self.econ_tabs, 1),
+                                KeyCode::Right | KeyCode::Down => timeline.scroll_down(&self.econ_tabs, 1),
+                                KeyCode::PageUp => timeline.scroll_up(&self.econ_tabs, page),
+                                KeyCode::PageDown => timeline.scroll_down(&self.econ_tabs, page),
+                                KeyCode::Home => timeline.scroll_to_top(&self.econ_tabs),
+                                KeyCode::End => timeline.scroll_to_bottom(),
+                                KeyCode::Char('f') => data.filtering = true,
+                                KeyCode::Char('t') => self.config.show_timestamps = !self.config.show_timestamps,
+                                KeyCode::Char('T') => {
+                                    self.config.timestamp_format = self.config.timestamp_format.next();
+                                    self.config.show_timestamps = true;
+                                },
+                                KeyCode::Char('F') => {
+                                    timeline.filter.enabled = !timeline.filter.enabled;
+                                    timeline.refilter();
+                                },
+                                KeyCode::Char('c') => {
+                                    timeline.filter.category = timeline.filter.category.next();
+                                    timeline.filter.enabled = true;
+                                    timeline.refilter();
+                                },
+                                KeyCode::Char('C') => {
+                                    timeline.filter.category = timeline.filter.category.prev();
+                                    timeline.filter.enabled = true;
+                                    timeline.refilter();
+                                },
+                                KeyCode::Char('m') => {
+                                    timeline.filter.mode = timeline.filter.mode.next();
+                                    timeline.refilter();
+                                },
+                                _ => Self::on_pane_key(data, self.econ_tabs.len(), key)
                             }
-                            else {
-                                data.connection = 0
-                            },
-                            _ => {}
                         },
                         MainElements::Console => if !self.econ_tabs.is_empty() {
+                            let tabs = self.econ_tabs.len();
                             let econ_tab = self.econ_tabs.get_mut(data.connection as _).unwrap();
+                            let page = self.console_areas.get(data.pane).map_or(1, |area| area.height.saturating_sub(2).max(1)) as usize;
                             match key.code {
-                                KeyCode::Left => {
-                                    if econ_tab.scroll > 0 {
-                                        econ_tab.scroll -= 1;
-                                    }
+                                KeyCode::Left | KeyCode::Up => econ_tab.scroll_up(1),
+                                KeyCode::Right | KeyCode::Down => econ_tab.scroll_down(1),
+                                KeyCode::PageUp => econ_tab.scroll_up(page),
+                                KeyCode::PageDown => econ_tab.scroll_down(page),
+                                KeyCode::Home => econ_tab.scroll_to_top(),
+                                KeyCode::End => econ_tab.scroll_to_bottom(),
+                                KeyCode::Char('f') => data.filtering = true,
+                                KeyCode::Char('/') => {
+                                    econ_tab.find = Some(Find::new());
+                                    data.finding = true;
                                 },
-                                KeyCode::Right => {
-                                    if econ_tab.scroll < econ_tab.messages.len() as _ {
-                                        econ_tab.scroll += 1;
+                                KeyCode::Char('n') => econ_tab.find_next(Some(true)),
+                                KeyCode::Char('N') => econ_tab.find_next(Some(false)),
+                                KeyCode::Char('t') => self.config.show_timestamps = !self.config.show_timestamps,
+                                KeyCode::Char('T') => {
+                                    self.config.timestamp_format = self.config.timestamp_format.next();
+                                    self.config.show_timestamps = true;
+                                },
+                                KeyCode::Char('v') => econ_tab.toggle_mark(),
+                                KeyCode::Char('e') => {
+                                    let scope = if econ_tab.mark.is_some() {
+                                        ExportScope::Marked
+                                    }
+                                    else if econ_tab.filter.active() {
+                                        ExportScope::Filtered
                                     }
+                                    else {
+                                        ExportScope::All
+                                    };
+                                    data.export = Some(Export::new(&econ_tab.name, scope, econ_tab.export_lines(scope).len()));
+                                },
+                                KeyCode::Char('[') => econ_tab.select_command(true),
+                                KeyCode::Char(']') => econ_tab.select_command(false),
+                                KeyCode::Char('z') => econ_tab.toggle_collapsed(),
+                                KeyCode::Char('y') => if let Some(command) = econ_tab.selected_command {
+                                    let text: Vec<&str> = econ_tab.command_group(command).iter().map(|l| l.raw.as_str()).collect();
+                                    let _ = clipboard::copy(&text.join("\n"));
+                                },
+                                KeyCode::Char('r') => if let Some(line) = econ_tab.selected_command() {
+                                    let command = line.text.clone();
+                                    econ_tab.history.push(&command);
+                                    let _ = econ_tab.send(command);
+                                    econ_tab.scroll_to_bottom();
+                                },
+                                KeyCode::Char('F') => {
+                                    econ_tab.filter.enabled = !econ_tab.filter.enabled;
+                                    econ_tab.refilter();
+                                },
+                                KeyCode::Char('c') => {
+                                    econ_tab.filter.category = econ_tab.filter.category.next();
+                                    econ_tab.filter.enabled = true;
+                                    econ_tab.refilter();
+                                },
+                                KeyCode::Char('C') => {
+                                    econ_tab.filter.category = econ_tab.filter.category.prev();
+                                    econ_tab.filter.enabled = true;
+                                    econ_tab.refilter();
+                                },
+                                KeyCode::Char('m') => {
+                                    econ_tab.filter.mode = econ_tab.filter.mode.next();
+                                    econ_tab.refilter();
+                                },
+                                _ => Self::on_pane_key(data, tabs, key)
+                            }
+                        },
+                        MainElements::Players => if let Some(econ_tab) = self.econ_tabs.get(data.connection as _) {
+                            match key.code {
+                                KeyCode::Up => data.player = data.player.saturating_sub(1),
+                                KeyCode::Down => data.player = (data.player + 1).min(econ_tab.players.len().saturating_sub(1)),
+                                KeyCode::Enter => if let Some(player) = econ_tab.players.values().nth(data.player) {
+                                    data.menu = Some(PlayerMenu::new(player.id, player.name.clone()));
                                 },
                                 _ => {}
                             }
                         },
-                        // todo: replace it with tui_input crate
                         MainElements::Input => if !self.econ_tabs.is_empty() {
                             if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
-                                match key.code {
-                                    KeyCode::Backspace => {
-                                        econ_tab.buffer.pop();
-                                    },
-                                    KeyCode::Char(c) => econ_tab.buffer.push(c),
-                                    KeyCode::Enter => {
-                                        econ_tab.connection.send(econ_tab.buffer.drain(..).collect::<String>()).unwrap();
+                                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+                                if searching {
+                                    match key.code {
+                                        KeyCode::Char('r') if ctrl => econ_tab.history.search_older(),
+                                        KeyCode::Char(c) => econ_tab.history.search_push(c),
+                                        KeyCode::Backspace => econ_tab.history.search_pop(),
+                                        KeyCode::Esc => {
+                                            econ_tab.history.stop_search();
+                                        },
+                                        _ => if let Some(command) = econ_tab.history.stop_search() {
+                                            econ_tab.buffer = Input::new(command);
+                                        }
+                                    }
+                                }
+                                else if let Some(completion) = &mut econ_tab.completion {
+                                    match key.code {
+                                        KeyCode::Tab => completion.cycle(&mut econ_tab.buffer, false),
+                                        KeyCode::BackTab => completion.cycle(&mut econ_tab.buffer, true),
+                                        KeyCode::Esc | KeyCode::Enter => econ_tab.completion = None,
+                                        _ => {
+                                            econ_tab.completion = None;
+                                            lineedit::handle(&mut econ_tab.buffer, key);
+                                        }
+                                    }
+                                }
+                                else {
+                                    match key.code {
+                                        KeyCode::Tab => econ_tab.completion = Completion::start(&mut econ_tab.buffer, &econ_tab.players),
+                                        KeyCode::Char('r') if ctrl => econ_tab.history.start_search(),
+                                        KeyCode::Up => if let Some(command) = econ_tab.history.older(econ_tab.buffer.value()) {
+                                            econ_tab.buffer = Input::new(command.to_string());
+                                        },
+                                        KeyCode::Down => if let Some(command) = econ_tab.history.newer() {
+                                            econ_tab.buffer = Input::new(command.to_string());
+                                        },
+                                        KeyCode::Enter => {
+                                            let command = econ_tab.buffer.value().to_string();
+                                            econ_tab.buffer.reset();
+                                            econ_tab.history.push(&command);
+                                            let _ = econ_tab.send(command);
+                                        }
+                                        _ => {
+                                            lineedit::handle(&mut econ_tab.buffer, key);
+                                        }
                                     }
-                                    _ => {}
                                 }
                             }
                         },
                         MainElements::Add => match key.code {
                             KeyCode::Enter => {
-                                self.current_screen = Screen::AddConnection(AddConnection::new());
+                                self.current_screen = Screen::AddConnection(AddConnection::new(data.clone()));
                             },
                             _ => {}
                         }
@@ -88,31 +432,102 @@ impl App {
             },
             Screen::AddConnection(data) => {
                 match key.code {
-                    KeyCode::Up => data.active = data.active.prev(),
-                    KeyCode::Down => data.active = data.active.next(),
-                    KeyCode::Esc => self.current_screen = Screen::Main(Main::new()),
+                    KeyCode::Up | KeyCode::BackTab => data.active = data.active.prev(),
+                    KeyCode::Down | KeyCode::Tab => data.active = data.active.next(),
+                    KeyCode::Esc => {
+                        self.pending = None;
+                        self.lookup = None;
+                        self.current_screen = Screen::Main(data.main.clone());
+                    },
 
                     _ => match data.active {
-                        AddConnectionElements::OkButton => match key.code {
-                            KeyCode::Enter => {
-                                if let Some(econ_tab) = Self::process_connection_data(data.fields) {
-                                    self.econ_tabs.push_back(econ_tab);
+                        AddConnectionElements::OkButton | AddConnectionElements::SaveButton => if key.code == KeyCode::Enter && !data.pending {
+                            let profile = Self::process_connection_data(&data.fields);
+                            let save = data.active == AddConnectionElements::SaveButton;
+                            match data.chosen() {
+                                Some(address) => self.pending = Self::begin_connect(data, &self.config, profile, address, save),
+                                None => {
+                                    self.lookup = Some(PendingLookup { lookup: Lookup::start(&profile.address), profile, save });
+                                    data.pending = true;
+                                    data.error = None;
                                 }
-                                self.current_screen = Screen::Main(Main::new());
-                            },
-                            _ => {}
+                            }
                         },
-                        // todo: replace it with tui_input crate
                         _ => match key.code {
-                            KeyCode::Backspace => data.fields[data.active as usize].pop(),
-                            KeyCode::Char(c) => data.fields[data.active as usize].push(c),
-                            _ => {}
+                            KeyCode::PageUp if data.resolved_count() > 1 => {
+                                data.choice = (data.choice + data.resolved_count() - 1) % data.resolved_count();
+                            },
+                            KeyCode::PageDown if data.resolved_count() > 1 => {
+                                data.choice = (data.choice + 1) % data.resolved_count();
+                            },
+                            _ => if lineedit::handle(&mut data.fields[data.active as usize], key) {
+                                data.field_changed();
+                            }
                         }
                     }
                 }
             },
-            Screen::Exit => match key.code {
-                KeyCode::Esc => self.current_screen = Screen::Main(Main::new()),
+            Screen::Bans(data) => {
+                let econ_tab = match self.econ_tabs.get_mut(data.main.connection as _) {
+                    Some(econ_tab) => econ_tab,
+                    None => {
+                        self.current_screen = Screen::Main(data.main.clone());
+                        return false;
+                    }
+                };
+
+                if let Some(form) = &mut data.form {
+                    match key.code {
+                        KeyCode::Esc => data.form = None,
+                        KeyCode::Enter => if let Some(command) = form.command() {
+                            let _ = econ_tab.send(command);
+                            econ_tab.request_bans();
+                            data.form = None;
+                        },
+                        KeyCode::Up | KeyCode::BackTab => form.active = form.active.prev(),
+                        KeyCode::Down | KeyCode::Tab => form.active = form.active.next(),
+                        // the address of an existing ban is what gets extended
+                        _ if form.active == BanFormElements::Address && form.kind != BanFormKind::Add => {},
+                        KeyCode::Char(c) if form.active == BanFormElements::Minutes && !c.is_ascii_digit() => {},
+                        _ => {
+                            lineedit::handle(&mut form.fields[form.active as usize], key);
+                        }
+                    }
+                }
+                else if let Some(ban) = &data.removing {
+                    match key.code {
+                        KeyCode::Enter | KeyCode::Char('y') => {
+                            let _ = econ_tab.send(ban.unban());
+                            econ_tab.request_bans();
+                            data.removing = None;
+                        },
+                        KeyCode::Esc | KeyCode::Char('n') => data.removing = None,
+                        _ => {}
+                    }
+                }
+                else {
+                    let selected = econ_tab.bans.get(data.selected);
+                    match key.code {
+                        KeyCode::Esc => self.current_screen = Screen::Main(data.main.clone()),
+                        KeyCode::Up => data.selected = data.selected.saturating_sub(1),
+                        KeyCode::Down => data.selected = (data.selected + 1).min(econ_tab.bans.len().saturating_sub(1)),
+                        KeyCode::Char('r') => econ_tab.request_bans(),
+                        KeyCode::Char('a') => data.form = Some(BanForm::add()),
+                        KeyCode::Char('e') => if let Some(ban) = selected {
+                            // permanent bans can not get any longer
+                            if let Some(remaining) = econ_tab.ban_remaining(ban) {
+                                data.form = Some(BanForm::extend(ban, remaining));
+                            }
+                        },
+                        KeyCode::Char('d') | KeyCode::Delete => if let Some(ban) = selected {
+                            data.removing = Some(ban.clone());
+                        },
+                        _ => {}
+                    }
+                }
+            },
+            Screen::Exit(main) => match key.code {
+                KeyCode::Esc => self.current_screen = Screen::Main(main.clone()),
                 KeyCode::Enter => return true,
                 _ => {}
             },
@@ -121,58 +536,351 @@ impl App {
         false
     }
 
-    pub fn on_tick(&mut self) {
-        if !self.econ_tabs.is_empty() {
-            let mut dead_connections = Vec::new();
-            for (id, econ_tab) in self.econ_tabs.iter().enumerate() {
-                if !econ_tab.connection.alive() {
-                    dead_connections.push(id);
+    // returns false once the broadcast window should close
+    fn on_broadcast_key(broadcast: &mut Broadcast, marked: &BTreeSet<u8>, econ_tabs: &mut VecDeque<EconTab>, key: KeyEvent) -> bool {
+        if !broadcast.results.is_empty() {
+            return !matches!(key.code, KeyCode::Esc | KeyCode::Enter);
+        }
+
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Enter if !broadcast.command.value().trim().is_empty() => {
+                let command = broadcast.command.value().to_string();
+
+                for &connection in marked {
+                    let econ_tab = match econ_tabs.get_mut(connection as usize) {
+                        Some(econ_tab) => econ_tab,
+                        None => continue
+                    };
+                    let since = econ_tab.received;
+                    let error = if econ_tab.state != ConnectionState::Authenticated {
+                        Some("not connected".to_string())
+                    }
+                    else {
+                        econ_tab.history.push(&command);
+                        econ_tab.send(command.clone()).err().map(|e| e.to_string())
+                    };
+
+                    broadcast.results.push(BroadcastResult { connection, error, since });
+                }
+            },
+            _ => {
+                lineedit::handle(&mut broadcast.command, key);
+            }
+        }
+
+        true
+    }
+
+    // splitting, closing, focusing and resizing console panes
+    fn on_pane_key(data: &mut Main, tabs: usize, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('s') if tabs > 0 => data.split_pane(((data.connection as usize + 1) % tabs) as u8),
+            KeyCode::Char('q') => data.close_pane(),
+            KeyCode::Char('w') => data.focus_pane((data.pane + 1) % data.panes.len()),
+            KeyCode::Char('W') => data.focus_pane((data.pane + data.panes.len() - 1) % data.panes.len()),
+            KeyCode::Char('o') => data.split = data.split.next(),
+            KeyCode::Char('+') => data.resize_pane(true),
+            KeyCode::Char('-') => data.resize_pane(false),
+            _ => {}
+        }
+    }
+
+    fn on_menu_key(menu: &mut PlayerMenu, key: KeyEvent) -> MenuOutcome {
+        let advance = |menu: &mut PlayerMenu| match menu.next_step() {
+            Some(step) => {
+                menu.step = step;
+                MenuOutcome::Open
+            },
+            None => MenuOutcome::Send(menu.command())
+        };
+
+        match (menu.step, key.code) {
+            (_, KeyCode::Esc) => MenuOutcome::Close,
+            (PlayerMenuStep::Choose, KeyCode::Up) => {
+                menu.action = menu.action.prev();
+                MenuOutcome::Open
+            },
+            (PlayerMenuStep::Choose, KeyCode::Down) => {
+                menu.action = menu.action.next();
+                MenuOutcome::Open
+            },
+            (PlayerMenuStep::Confirm, KeyCode::Enter | KeyCode::Char('y')) => MenuOutcome::Send(menu.command()),
+            (PlayerMenuStep::Confirm, KeyCode::Char('n')) => MenuOutcome::Close,
+            (_, KeyCode::Enter) => advance(menu),
+            // durations are plain numbers
+            (PlayerMenuStep::Duration, KeyCode::Char(c)) if !c.is_ascii_digit() => MenuOutcome::Open,
+            (PlayerMenuStep::Duration, _) => {
+                lineedit::handle(&mut menu.duration, key);
+                MenuOutcome::Open
+            },
+            (PlayerMenuStep::Reason, _) => {
+                lineedit::handle(&mut menu.reason, key);
+                MenuOutcome::Open
+            },
+            _ => MenuOutcome::Open
+        }
+    }
+
+    pub fn on_paste(&mut self, text: String) {
+        match &mut self.current_screen {
+            Screen::Main(Main { broadcast: Some(broadcast), .. }) if broadcast.results.is_empty() => {
+                lineedit::paste(&mut broadcast.command, &text);
+            },
+            Screen::Main(Main { broadcast: Some(_), .. }) => {},
+            Screen::Main(Main { export: Some(export), .. }) if export.result.is_none() => {
+                lineedit::paste(&mut export.path, &text);
+            },
+            Screen::Main(data) if data.finding => {
+                if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
+                    if let Some(find) = &mut econ_tab.find {
+                        lineedit::paste(&mut find.query, &text);
+                    }
+                    econ_tab.refind();
+                }
+            },
+            Screen::Main(data) if data.filtering && data.timeline => {
+                lineedit::paste(&mut self.timeline.filter.query, &text);
+                self.timeline.refilter();
+            },
+            Screen::Main(data) if data.filtering => {
+                if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
+                    lineedit::paste(&mut econ_tab.filter.query, &text);
+                    econ_tab.refilter();
+                }
+            },
+            Screen::Main(Main { menu: Some(menu), .. }) if menu.step == PlayerMenuStep::Reason => {
+                lineedit::paste(&mut menu.reason, &text);
+            },
+            Screen::Main(Main { menu: Some(_), .. }) => {},
+            Screen::Main(data) if data.active == MainElements::Input => {
+                if let Some(econ_tab) = self.econ_tabs.get_mut(data.connection as _) {
+                    lineedit::paste(&mut econ_tab.buffer, &text);
+                }
+            },
+            Screen::Bans(Bans { form: Some(form), .. }) if form.active != BanFormElements::Address || form.kind == BanFormKind::Add => {
+                lineedit::paste(&mut form.fields[form.active as usize], &text);
+            },
+            Screen::AddConnection(data) if (data.active as usize) < data.fields.len() => {
+                lineedit::paste(&mut data.fields[data.active as usize], &text);
+                data.field_changed();
+            },
+            _ => {}
+        }
+    }
+
+    pub fn on_mouse(&mut self, mouse: MouseEvent) {
+        const WHEEL_LINES: usize = 3;
+
+        if let Screen::Main(data) = &self.current_screen {
+            let pane = self.console_areas.iter().position(|area| {
+                mouse.column >= area.x && mouse.column < area.x + area.width
+                    && mouse.row >= area.y && mouse.row < area.y + area.height
+            });
+
+            if pane.is_some_and(|pane| data.pane_timeline(pane)) {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => self.timeline.scroll_up(&self.econ_tabs, WHEEL_LINES),
+                    MouseEventKind::ScrollDown => self.timeline.scroll_down(&self.econ_tabs, WHEEL_LINES),
+                    _ => {}
                 }
             }
-    
-            dead_connections.reverse();
-    
-            for id in dead_connections {
-                self.econ_tabs.remove(id);
+            else if let Some(econ_tab) = pane.and_then(|pane| self.econ_tabs.get_mut(data.pane_connection(pane) as usize)) {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => econ_tab.scroll_up(WHEEL_LINES),
+                    MouseEventKind::ScrollDown => econ_tab.scroll_down(WHEEL_LINES),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    pub fn on_tick(&mut self) {
+        if let Some(result) = self.lookup.as_ref().and_then(|pending| pending.lookup.poll()) {
+            self.finish_lookup(result);
+        }
+
+        if let Some(pending) = &mut self.pending {
+            pending.econ_tab.poll();
+
+            let result = match pending.econ_tab.state {
+                ConnectionState::Authenticated => Some(Ok(())),
+                ConnectionState::AuthFailed => Some(Err(ConnectError::WrongPassword)),
+                _ if !pending.econ_tab.connection.alive() => Some(Err(ConnectError::Closed)),
+                _ if pending.started.elapsed() >= AUTH_TIMEOUT => Some(Err(ConnectError::Timeout)),
+                _ => None
+            };
+
+            if let Some(result) = result {
+                self.finish_pending(result);
             }
-    
+        }
+
+        if !self.econ_tabs.is_empty() {
+            let status_interval = Duration::from_secs(self.config.status_interval_secs);
+
             for econ_tab in &mut self.econ_tabs {
-                // 1 connection = 1 ms
-                if let Ok(messages) = econ_tab.connection.recv() {
-                    let messages = messages
-                        .lines()
-                        .map(|s| s.to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<String>>();
-                    econ_tab.messages.extend(messages);
+                match econ_tab.state {
+                    ConnectionState::Reconnecting(mut backoff) => {
+                        if !backoff.ready() {
+                            continue;
+                        }
+
+                        // the host may have moved since the last attempt
+                        let lookup = econ_tab.lookup.get_or_insert_with(|| Lookup::start(&econ_tab.host));
+                        match lookup.poll() {
+                            None => continue,
+                            Some(Ok(addresses)) if !addresses.is_empty() => {
+                                // stay on the address picked in AddConnection while the host still has it
+                                econ_tab.address = econ_tab.address.filter(|a| addresses.contains(a)).or(addresses.first().copied());
+                                econ_tab.lookup = None;
+                            },
+                            Some(_) => {
+                                econ_tab.lookup = None;
+                                backoff.fail();
+                                econ_tab.state = ConnectionState::Reconnecting(backoff);
+                                continue;
+                            }
+                        }
+
+                        if econ_tab.launch().is_err() {
+                            backoff.fail();
+                            econ_tab.state = ConnectionState::Reconnecting(backoff);
+                            continue;
+                        }
+                        // counted until the server lets us log in
+                        econ_tab.attempts = backoff.attempts.saturating_add(1);
+                    },
+                    // a wrong password would only get us banned if retried
+                    ConnectionState::AuthFailed | ConnectionState::Disconnected => continue,
+                    _ => if !econ_tab.connection.alive() {
+                        econ_tab.state = ConnectionState::Reconnecting(Backoff::after(econ_tab.attempts));
+                        continue;
+                    }
+                }
+
+                econ_tab.poll();
+
+                if econ_tab.state == ConnectionState::Authenticated && econ_tab.last_status.elapsed() >= status_interval {
+                    econ_tab.request_status();
                 }
             }
         }
     }
 
-    fn process_connection_data(data: [StringArray<64>; 3]) -> Option<EconTab> {
-        let name = data[AddConnectionElements::Name as usize].to_string();
-        let address = match data[AddConnectionElements::Address as usize].to_string().parse::<SocketAddr>() {
-            Ok(address) => address,
-            Err(_) => return None
+    fn finish_pending(&mut self, result: Result<(), ConnectError>) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return
         };
-        let password = data[AddConnectionElements::Password as usize].to_string();
 
-        let mut connection = Connection::new();
+        match result {
+            Ok(()) => {
+                let mut econ_tab = pending.econ_tab;
+                if pending.save {
+                    self.profiles.insert(pending.profile);
+                    // the connection works, only the next start would miss it
+                    if let Err(error) = Self::save_profiles(&self.profiles, &self.profiles_error) {
+                        econ_tab.notice(format!("could not save the connection: {}", error));
+                    }
+                }
+                self.econ_tabs.push_back(econ_tab);
+                if let Screen::AddConnection(data) = &self.current_screen {
+                    self.current_screen = Screen::Main(data.main.clone());
+                }
+            },
+            Err(error) => if let Screen::AddConnection(data) = &mut self.current_screen {
+                data.pending = false;
+                data.error = Some(error);
+                data.active = error.field();
+            }
+        }
+    }
+
+    fn save_profiles(profiles: &Profiles, error: &Option<String>) -> io::Result<()> {
+        match error {
+            Some(_) => Err(io::Error::other("profiles.toml could not be loaded, fix it and restart to save")),
+            None => profiles.save()
+        }
+    }
 
-        if let Err(_) = connection.launch_with_password(address, password) {
-            return None
+    fn process_connection_data(data: &[Input; 4]) -> Profile {
+        Profile {
+            name: data[AddConnectionElements::Name as usize].to_string(),
+            address: data[AddConnectionElements::Address as usize].to_string(),
+            password: data[AddConnectionElements::Password as usize].to_string(),
+            group: data[AddConnectionElements::Group as usize].value().trim().to_string(),
+            log: LogFormat::Off
         }
+    }
 
-        let econ_tab = EconTab {
-            name,
-            connection,
-            messages: Vec::new(),
-            buffer: String::new(),
-            scroll: 0
+    // a host that resolved to several addresses waits for the user to pick one and press Enter again
+    fn finish_lookup(&mut self, result: io::Result<Vec<SocketAddr>>) {
+        let (pending, data) = match (self.lookup.take(), &mut self.current_screen) {
+            (Some(pending), Screen::AddConnection(data)) => (pending, data),
+            _ => return
         };
 
-        Some(econ_tab)
+        data.pending = false;
+
+        let addresses = match result {
+            Ok(addresses) if addresses.is_empty() => Err(ConnectError::Unresolved),
+            Ok(addresses) => Ok(addresses),
+            Err(error) if error.kind() == io::ErrorKind::InvalidInput => Err(ConnectError::InvalidAddress),
+            Err(_) => Err(ConnectError::Unresolved)
+        };
+
+        match addresses {
+            Ok(addresses) => {
+                data.set_resolved(&addresses);
+                match data.chosen() {
+                    Some(address) if addresses.len() == 1 => {
+                        self.pending = Self::begin_connect(data, &self.config, pending.profile, address, pending.save);
+                    },
+                    _ => data.active = AddConnectionElements::Address
+                }
+            },
+            Err(error) => {
+                data.error = Some(error);
+                data.active = error.field();
+            }
+        }
+    }
+
+    // the connection waits in `pending` until the server answers the password, see on_tick
+    fn begin_connect(data: &mut AddConnection, config: &Config, profile: Profile, address: SocketAddr, save: bool) -> Option<PendingConnection> {
+        let mut econ_tab = Self::econ_tab(&profile, Some(address), config);
+
+        match econ_tab.launch() {
+            Ok(()) => {
+                data.pending = true;
+                data.error = None;
+                Some(PendingConnection { econ_tab, profile, save, started: Instant::now() })
+            },
+            Err(error) => {
+                let error = ConnectError::from(error);
+                data.error = Some(error);
+                data.active = error.field();
+                None
+            }
+        }
+    }
+
+    fn econ_tab(profile: &Profile, address: Option<SocketAddr>, config: &Config) -> EconTab {
+        EconTab {
+            group: profile.group.clone(),
+            log: SessionLog::new(&profile.name, profile.log, config.log_max_bytes),
+            ..EconTab::new(profile.name.clone(), profile.address.clone(), address, profile.password.clone(), config.scrollback_lines)
+        }
+    }
+
+    // saved profiles are kept even if their host does not resolve or the server is down,
+    // on_tick looks them up and connects once the first frame is drawn
+    fn restore(profile: &Profile, config: &Config) -> EconTab {
+        EconTab {
+            state: ConnectionState::Reconnecting(Backoff::now()),
+            ..Self::econ_tab(profile, None, config)
+        }
     }
 }
 
@@ -188,6 +896,9 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
     f.render_widget(background, size);
 
+    // the "All" timeline only filters what arrived since the last frame
+    app.timeline.sync(&app.econ_tabs);
+
     match &app.current_screen {
         Screen::Welcome => {
             const WELCOME_TEXT: &str = "tw-econ-tui\n\nThis is a multi-windowed Teeworlds external console.\nYou can freely switch between different connections, like if it was a brand new console shell.\n\nPress Enter to continue";
@@ -197,19 +908,45 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 .wrap(Wrap { trim: false });
 
             f.render_widget(paragraph, centered_rect(30, 50, Rect::new(0, 0, 30, 8), size));
+
+            if let Some(error) = &app.profiles_error {
+                let area = Rect::new(size.x, size.bottom().saturating_sub(3), size.width, 3.min(size.height));
+                let warning = Paragraph::new(profiles_warning(error))
+                    .style(Style::default().fg(Color::Red))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+
+                f.render_widget(warning, area);
+            }
         },
         Screen::Main(data) => {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(5)
-                .constraints([Constraint::Length(3), Constraint::Min(3), Constraint::Length(3), Constraint::Length(3)].as_ref())
+                .constraints([Constraint::Min(3), Constraint::Length(3), Constraint::Length(3)].as_ref())
                 .split(size);
 
-            let connection_titles = app
-                .econ_tabs
+            let tree = connection_tree(&app.econ_tabs, &data.collapsed);
+            let connection_items: Vec<ListItem> = tree
                 .iter()
-                .map(|t| {
-                    Spans::from(vec![Span::styled(t.name.clone(), default_style)])
+                .map(|item| match item {
+                    TreeItem::Group(group) => {
+                        let arrow = if data.collapsed.contains(group) { "▸" } else { "▾" };
+                        let count = group_members(&app.econ_tabs, group).len();
+                        ListItem::new(Span::styled(format!("{} {} ({})", arrow, group, count), default_style.add_modifier(Modifier::BOLD)))
+                    },
+                    TreeItem::All => ListItem::new(Span::styled("≡ All", default_style.add_modifier(Modifier::BOLD))),
+                    TreeItem::Connection(i) => {
+                        let t = &app.econ_tabs[*i as usize];
+                        let indent = if t.group.is_empty() { "" } else { "  " };
+                        let title = if data.marked.contains(i) {
+                            Span::styled(format!("* {}", t.title()), default_style.add_modifier(Modifier::UNDERLINED))
+                        }
+                        else {
+                            Span::styled(t.title(), default_style)
+                        };
+                        ListItem::new(Spans::from(vec![Span::raw(indent), Span::styled("● ", Style::default().fg(state_color(&t.state))), title]))
+                    }
                 })
                 .collect();
 
@@ -226,39 +963,142 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                     .fg(Color::White)
             };
 
-            let mut connections = Tabs::new(connection_titles)
+            let mut connections_state = ListState::default();
+            if !tree.is_empty() {
+                connections_state.select(Some(tree_cursor(&tree, data, &app.econ_tabs)));
+            }
+
+            let mut connections = List::new(connection_items)
                 .block(default_block.clone().title("Connections"))
-                .select(data.connection as _)
                 .style(default_style)
                 .highlight_style(highlight_style);
 
-            let console_scroll = (0, 0);
-            let mut console_content = String::new();
+            let body = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(CONNECTIONS_WIDTH), Constraint::Min(20), Constraint::Length(PLAYERS_WIDTH)].as_ref())
+                .split(chunks[0]);
+            let filter = if data.timeline { Some(&app.timeline.filter) } else { app.econ_tabs.get(data.connection as _).map(|t| &t.filter) };
+            let show_filter = data.filtering || filter.is_some_and(|f| !f.query.value().is_empty() || f.category != Category::All);
+            let find = app.econ_tabs.get(data.connection as _).and_then(|t| t.find.as_ref()).filter(|_| !data.timeline);
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(if show_filter { 3 } else { 0 }),
+                    Constraint::Min(3),
+                    Constraint::Length(if find.is_some() { 3 } else { 0 })
+                ].as_ref())
+                .split(body[1]);
+            let filter_area = Some(split[0]).filter(|_| show_filter);
+            let find_area = Some(split[2]).filter(|_| find.is_some());
+            let total_weight: u32 = data.panes.iter().map(|p| p.weight as u32).sum();
+            let split_direction = match data.split {
+                SplitDirection::Horizontal => Direction::Horizontal,
+                SplitDirection::Vertical => Direction::Vertical
+            };
+            let pane_areas = Layout::default()
+                .direction(split_direction)
+                .constraints(data.panes.iter().map(|p| Constraint::Ratio(p.weight as u32, total_weight)).collect::<Vec<_>>())
+                .split(split[1]);
+
+            app.console_areas = pane_areas.clone();
+
+            if let (Some(area), Some(filter)) = (filter_area, filter) {
+                let mut title = vec![Span::raw(format!(
+                    "Filter: {}, {}{}",
+                    filter.mode.label(),
+                    filter.category.label(),
+                    if filter.enabled { "" } else { " (off)" }
+                ))];
+                if let Some(error) = &filter.error {
+                    title.push(Span::styled(format!(" - {}", error), Style::default().fg(Color::Red)));
+                }
+                let block = if data.filtering { active_block.clone() } else { default_block.clone() };
+                let (scroll, (x, y)) = lineedit::layout(&filter.query, area);
+
+                if data.filtering {
+                    f.set_cursor(x, y);
+                }
+
+                let bar = Paragraph::new(filter.query.to_string())
+                    .style(if data.filtering { active_style } else { default_style })
+                    .scroll((0, scroll))
+                    .block(block.title(Spans::from(title)));
+
+                f.render_widget(bar, area);
+            }
+
+            if let (Some(area), Some(econ_tab)) = (find_area, app.econ_tabs.get(data.connection as _)) {
+                let find = econ_tab.find.as_ref().unwrap();
+                let matches = &find.matches;
+                let current = find.current.and_then(|current| matches.binary_search(&current).ok());
+                let title = match current {
+                    Some(current) => format!("Search ({}/{})", current + 1, matches.len()),
+                    None => format!("Search ({} matches)", matches.len())
+                };
+                let (scroll, (x, y)) = lineedit::layout(&find.query, area);
+
+                if data.finding {
+                    f.set_cursor(x, y);
+                }
+
+                let bar = Paragraph::new(find.query.to_string())
+                    .style(if data.finding { active_style } else { default_style })
+                    .scroll((0, scroll))
+                    .block(if data.finding { active_block.clone() } else { default_block.clone() }.title(title));
+
+                f.render_widget(bar, area);
+            }
+
             let mut input_content = String::new();
+            let mut input_scroll = 0;
+            let mut input_cursor = None;
 
             if let Some(econ_tab) = app.econ_tabs.get(data.connection as _) {
-                console_content = econ_tab.messages.join("\n");
-                input_content = econ_tab.buffer.clone();
+                input_content = match econ_tab.history.search() {
+                    Some(search) => format!("(reverse-i-search)'{}': {}", search.query, econ_tab.history.matched().unwrap_or_default()),
+                    None => {
+                        let (scroll, cursor) = lineedit::layout(&econ_tab.buffer, chunks[1]);
+                        input_scroll = scroll;
+                        input_cursor = Some(cursor);
+                        econ_tab.buffer.to_string()
+                    }
+                };
             }
-            
-            let mut console = Paragraph::new(console_content)
-                .style(default_style)
-                .alignment(Alignment::Left)
-                .wrap(Wrap { trim: true })
-                .scroll(console_scroll)
-                .block(default_block.clone().title("Console"));
+
+            // the "All" timeline has no prompt of its own, commands still go to the last connection
+            let input_title = match app.econ_tabs.get(data.connection as _) {
+                Some(econ_tab) if data.timeline => format!("Input - {}", econ_tab.name),
+                _ => String::from("Input")
+            };
 
             let mut input = Paragraph::new(input_content)
                 .style(default_style)
                 .alignment(Alignment::Left)
-                .wrap(Wrap { trim: false })
-                .block(default_block.clone().title("Input"));
+                .scroll((0, input_scroll))
+                .block(default_block.clone().title(input_title.clone()));
+
+            let add_text = if data.active == MainElements::Connections {
+                "Space: mark  Enter: broadcast  c: connect  x: disconnect  l: log  b: bans  Left/Right: fold group"
+            }
+            else if data.active == MainElements::Console && data.timeline {
+                "t/T: time  f: filter  F: on/off  c/C: category  m: text/regex  s: split  q: close pane  w/W: pane  o: orientation  +/-: size"
+            }
+            else if data.active == MainElements::Console {
+                "/: search  n/N: match  [/]: command  z: fold  y: copy  r: re-run  t/T: time  v: mark  e: export  f: filter  F: on/off  c/C: category  m: text/regex  s: split  q: close pane  w/W: pane  o: orientation  +/-: size"
+            }
+            else {
+                "Press Enter to add new connection"
+            };
 
-            let mut add = Paragraph::new("Press Enter to add new connection")
+            let add_title = match &app.profiles_error {
+                Some(error) => Spans::from(vec![Span::raw("Add"), Span::styled(format!(" - {}", profiles_warning(error)), Style::default().fg(Color::Red))]),
+                None => Spans::from("Add")
+            };
+            let mut add = Paragraph::new(add_text)
                 .style(default_style)
                 .alignment(Alignment::Center)
                 .wrap(Wrap { trim: false })
-                .block(default_block.clone().title("Add"));
+                .block(default_block.clone().title(add_title.clone()));
 
             match data.active {
                 MainElements::Connections => {
@@ -267,61 +1107,249 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                         .block(active_block.clone()
                         .title("Connections"));
                 },
-                MainElements::Console => {
-                    console = console
-                        .style(active_style)
-                        .block(active_block.clone()
-                        .title("Console"));
-                },
+                MainElements::Console => {},
                 MainElements::Input => {
                     input = input
                         .style(active_style)
                         .block(active_block.clone()
-                        .title("Input"));
+                        .title(input_title));
+
+                    if let Some((x, y)) = input_cursor {
+                        f.set_cursor(x, y);
+                    }
                 },
+                MainElements::Players => {},
                 MainElements::Add => {
                     add = add
                         .style(active_style)
                         .block(active_block.clone()
-                        .title("Add"));
+                        .title(add_title));
                 },
             }
 
-            f.render_widget(connections, chunks[0]);
-            f.render_widget(console, chunks[1]);
-            f.render_widget(input, chunks[2]);
-            f.render_widget(add, chunks[3]);
+            let mut players_state = TableState::default();
+            let players = if data.active == MainElements::Players {
+                players_state.select(Some(data.player));
+                players_table(app.econ_tabs.get(data.connection as _), active_style, active_block.clone())
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Black).fg(Color::White))
+            }
+            else {
+                players_table(app.econ_tabs.get(data.connection as _), default_style, default_block.clone())
+            };
+
+            f.render_stateful_widget(connections, body[0], &mut connections_state);
+            for (pane, area) in pane_areas.iter().enumerate() {
+                let (content, scroll, mut title) = match app.econ_tabs.get(data.pane_connection(pane) as usize) {
+                    _ if data.pane_timeline(pane) => timeline_view(app, *area),
+                    Some(econ_tab) => console_view(app, econ_tab, *area),
+                    None => (Vec::new(), 0, String::from("Console"))
+                };
+                let focused = data.active == MainElements::Console && pane == data.pane;
+
+                if data.panes.len() > 1 && pane == data.pane {
+                    title = format!("* {}", title);
+                }
+
+                let console = Paragraph::new(content)
+                    .style(if focused { active_style } else { default_style })
+                    .alignment(Alignment::Left)
+                    .wrap(Wrap { trim: true })
+                    .scroll((scroll, 0))
+                    .block(if focused { active_block.clone() } else { default_block.clone() }.title(title));
+
+                f.render_widget(console, *area);
+            }
+            f.render_stateful_widget(players, body[2], &mut players_state);
+            f.render_widget(input, chunks[1]);
+            f.render_widget(add, chunks[2]);
+
+            if let Some(completion) = app.econ_tabs.get(data.connection as _).and_then(|t| t.completion.as_ref()) {
+                const MAX_ROWS: usize = 8;
+
+                let rows = completion.candidates.len().min(MAX_ROWS) as u16 + 2;
+                let width = completion.candidates.iter().map(|c| c.label.chars().count()).max().unwrap_or(0) as u16 + 4;
+                let area = Rect::new(
+                    chunks[1].x,
+                    chunks[1].y.saturating_sub(rows),
+                    width.min(chunks[1].width),
+                    rows.min(chunks[1].y)
+                );
+
+                let items: Vec<ListItem> = completion.candidates.iter().map(|c| ListItem::new(c.label.clone())).collect();
+                let list = List::new(items)
+                    .style(active_style)
+                    .block(active_block.clone().title("Complete"))
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Black).fg(Color::White));
+
+                let mut state = ListState::default();
+                state.select(completion.selected);
+
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(list, area, &mut state);
+            }
+
+            if let Some(broadcast) = &data.broadcast {
+                let area = centered_rect(60, 60, Rect::new(0, 0, 40, 12), size);
+                let title = format!("Broadcast to {} connections", data.marked.len());
+
+                f.render_widget(Clear, area);
+
+                if broadcast.results.is_empty() {
+                    let field_area = Rect::new(area.x, area.y, area.width, 3.min(area.height));
+                    let (scroll, (x, y)) = lineedit::layout(&broadcast.command, field_area);
+
+                    let field = Paragraph::new(broadcast.command.to_string())
+                        .style(active_style)
+                        .scroll((0, scroll))
+                        .block(active_block.clone().title(title));
+
+                    f.render_widget(field, field_area);
+                    f.set_cursor(x, y);
+                }
+                else {
+                    let mut lines = Vec::new();
+                    for result in &broadcast.results {
+                        let econ_tab = match app.econ_tabs.get(result.connection as usize) {
+                            Some(econ_tab) => econ_tab,
+                            None => continue
+                        };
+                        let status = match &result.error {
+                            Some(error) => Span::styled(format!("failed: {}", error), Style::default().fg(Color::Red)),
+                            None => Span::styled("sent", Style::default().fg(Color::Green))
+                        };
+                        lines.push(Spans::from(vec![Span::styled(format!("{}: ", econ_tab.name), active_style.add_modifier(Modifier::BOLD)), status]));
+                        lines.extend(econ_tab.since(result.since).map(|l| Spans::from(format!("  {}", l.raw))));
+                    }
+
+                    let paragraph = Paragraph::new(lines)
+                        .style(active_style)
+                        .wrap(Wrap { trim: false })
+                        .block(active_block.clone().title(format!("{} - {}", title, broadcast.command.value())));
+
+                    f.render_widget(paragraph, area);
+                }
+            }
+
+            if let (Some(export), Some(econ_tab)) = (&data.export, app.econ_tabs.get(data.connection as _)) {
+                let area = centered_rect(50, 30, Rect::new(0, 0, 50, 9), size);
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+                    .split(area);
+                let title = format!(
+                    "Export {} of {} ({} lines) as {}",
+                    export.scope.label(),
+                    econ_tab.name,
+                    export.count,
+                    export.format.label()
+                );
+                let (scroll, (x, y)) = lineedit::layout(&export.path, rows[0]);
+
+                f.render_widget(Clear, area);
+
+                let field = Paragraph::new(export.path.to_string())
+                    .style(active_style)
+                    .scroll((0, scroll))
+                    .block(active_block.clone().title(title));
+
+                let status = match &export.result {
+                    Some(Ok(message)) => Span::styled(message.clone(), Style::default().fg(Color::Green)),
+                    Some(Err(message)) => Span::styled(message.clone(), Style::default().fg(Color::Red)),
+                    None if export.overwrite => Span::styled("The file already exists  Enter: overwrite  Esc: cancel", Style::default().fg(Color::Red)),
+                    None => Span::raw("Tab: scope  Shift-Tab: format  Enter: write  Esc: cancel")
+                };
+                let status = Paragraph::new(Spans::from(status))
+                    .style(active_style)
+                    .wrap(Wrap { trim: false })
+                    .block(Block::default().borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM).style(active_style));
+
+                f.render_widget(field, rows[0]);
+                f.render_widget(status, rows[1]);
+                if export.result.is_none() {
+                    f.set_cursor(x, y);
+                }
+            }
+
+            if let Some(menu) = &data.menu {
+                let area = centered_rect(40, 30, Rect::new(0, 0, 40, 9), size);
+                let title = format!("{} ({})", menu.name, menu.id);
+
+                f.render_widget(Clear, area);
+
+                match menu.step {
+                    PlayerMenuStep::Choose => {
+                        let items: Vec<ListItem> = PlayerAction::ALL.iter().map(|a| ListItem::new(a.label())).collect();
+                        let list = List::new(items)
+                            .style(active_style)
+                            .block(active_block.clone().title(title))
+                            .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Black).fg(Color::White));
+
+                        let mut state = ListState::default();
+                        state.select(PlayerAction::ALL.iter().position(|a| *a == menu.action));
+
+                        f.render_stateful_widget(list, area, &mut state);
+                    },
+                    PlayerMenuStep::Duration | PlayerMenuStep::Reason => {
+                        let (prompt, input) = match (menu.step, menu.action) {
+                            (PlayerMenuStep::Duration, PlayerAction::Ban) => ("Duration in minutes (5)", &menu.duration),
+                            (PlayerMenuStep::Duration, _) => ("Duration in seconds (60)", &menu.duration),
+                            _ => ("Reason", &menu.reason)
+                        };
+                        let field_area = Rect::new(area.x, area.y, area.width, 3.min(area.height));
+                        let (scroll, (x, y)) = lineedit::layout(input, field_area);
+
+                        let field = Paragraph::new(input.to_string())
+                            .style(active_style)
+                            .scroll((0, scroll))
+                            .block(active_block.clone().title(format!("{} - {}: {}", title, menu.action.label(), prompt)));
+
+                        f.render_widget(field, field_area);
+                        f.set_cursor(x, y);
+                    },
+                    PlayerMenuStep::Confirm => {
+                        let text = format!("{}\n\nPress Enter to send\nPress Escape to cancel", menu.command());
+                        let paragraph = Paragraph::new(text)
+                            .style(active_style)
+                            .alignment(Alignment::Left)
+                            .wrap(Wrap { trim: false })
+                            .block(active_block.clone().title(format!("{} - {}?", title, menu.action.label())));
+
+                        f.render_widget(paragraph, area);
+                    }
+                }
+            }
         },
         Screen::AddConnection(data) => {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints(
                     [
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20)
+                        Constraint::Ratio(1, 6),
+                        Constraint::Ratio(1, 6),
+                        Constraint::Ratio(1, 6),
+                        Constraint::Ratio(1, 6),
+                        Constraint::Ratio(1, 6),
+                        Constraint::Ratio(1, 6)
                     ].as_ref()
                 )
                 .split(
                     centered_rect(
                         30,
-                        40,
-                        Rect::new(0, 0, 12, 20),
+                        48,
+                        Rect::new(0, 0, 12, 24),
                         size
                     )
                 );
 
-                let title = Paragraph::new("Connection Data")
+                let title = Paragraph::new(if data.pending { "Connecting..." } else { "Connection Data" })
                         .alignment(Alignment::Center)
                         .style(active_style);
 
                 f.render_widget(title, chunks[0]);
 
-                let block_names = vec!["Name", "Address", "Password"];
+                let block_names = vec!["Name", "Address", "Password", "Group (optional)"];
                 
-                for i in 0..3 {
+                for i in 0..4 {
                     let mut field_style = default_style;
                     let mut block = default_block.clone().title(block_names[i]);
 
@@ -330,14 +1358,38 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                         block = block.style(active_style);
                     }
 
+                    let mut title = Span::raw(block_names[i]);
+
+                    if let Some(error) = data.error.filter(|e| e.field() as usize == i) {
+                        title = Span::styled(format!("{} - {}", block_names[i], error.message()), Style::default().fg(Color::Red));
+                        block = block.border_style(Style::default().fg(Color::Red));
+                    }
+                    else if i == AddConnectionElements::Address as usize && data.resolved_count() > 1 {
+                        if let Some(address) = data.chosen() {
+                            title = Span::raw(format!("{} - {} ({}/{}, PgUp/PgDn)", block_names[i], address, data.choice + 1, data.resolved_count()));
+                        }
+                    }
+
+                    let (field_scroll, (x, y)) = lineedit::layout(&data.fields[i], chunks[i + 1]);
+
+                    if data.active as usize == i {
+                        f.set_cursor(x, y);
+                    }
+
                     let field = Paragraph::new(data.fields[i].to_string())
-                        .alignment(Alignment::Center)
+                        .alignment(Alignment::Left)
+                        .scroll((0, field_scroll))
                         .style(field_style)
-                        .block(block.clone().title(block_names[i]));
+                        .block(block.clone().title(title));
 
                     f.render_widget(field, chunks[i + 1]);
                 }
 
+                let buttons = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(chunks[5]);
+
                 let ok_style = if data.active == AddConnectionElements::OkButton {
                     active_style
                 }
@@ -349,9 +1401,102 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                     .style(ok_style)
                     .alignment(Alignment::Center);
 
-                f.render_widget(ok_button, chunks[4]);
+                f.render_widget(ok_button, buttons[0]);
+
+                let save_style = if data.active == AddConnectionElements::SaveButton {
+                    active_style
+                }
+                else {
+                    default_style
+                };
+
+                let save_button = Paragraph::new("Save & Add")
+                    .style(save_style)
+                    .alignment(Alignment::Center);
+
+                f.render_widget(save_button, buttons[1]);
+        },
+        Screen::Bans(data) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(5)
+                .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+                .split(size);
+
+            let econ_tab = app.econ_tabs.get(data.main.connection as _);
+
+            let mut bans_state = TableState::default();
+            bans_state.select(Some(data.selected));
+
+            let bans = bans_table(econ_tab, active_style, active_block.clone())
+                .highlight_style(Style::default().add_modifier(Modifier::BOLD).bg(Color::Black).fg(Color::White));
+
+            let help = Paragraph::new("a: add  e: extend  d: remove  r: refresh  Esc: back")
+                .style(default_style)
+                .alignment(Alignment::Center)
+                .block(default_block.clone());
+
+            f.render_stateful_widget(bans, chunks[0], &mut bans_state);
+            f.render_widget(help, chunks[1]);
+
+            if let Some(form) = &data.form {
+                let area = centered_rect(40, 30, Rect::new(0, 0, 40, 12), size);
+                let fields = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3), Constraint::Min(1)].as_ref())
+                    .split(area);
+
+                let minutes_title = match form.kind {
+                    BanFormKind::Add => "Minutes (5)".to_string(),
+                    BanFormKind::Extend(remaining) => format!("Minutes to add ({} left)", remaining)
+                };
+                let titles = ["Address", minutes_title.as_str(), "Reason"];
+
+                f.render_widget(Clear, area);
+
+                for (i, title) in titles.iter().enumerate() {
+                    let block = if form.active as usize == i {
+                        active_block.clone()
+                    }
+                    else {
+                        default_block.clone()
+                    };
+                    let (scroll, (x, y)) = lineedit::layout(&form.fields[i], fields[i]);
+
+                    if form.active as usize == i {
+                        f.set_cursor(x, y);
+                    }
+
+                    let field = Paragraph::new(form.fields[i].to_string())
+                        .style(active_style)
+                        .scroll((0, scroll))
+                        .block(block.title(*title));
+
+                    f.render_widget(field, fields[i]);
+                }
+
+                let hint = match form.command() {
+                    Some(command) => format!("Enter: {}", command),
+                    None => "Esc: cancel".to_string()
+                };
+
+                f.render_widget(Paragraph::new(hint).style(active_style).wrap(Wrap { trim: false }), fields[3]);
+            }
+
+            if let Some(ban) = &data.removing {
+                let text = format!("{}\n\nPress Enter to send\nPress Escape to cancel", ban.unban());
+                let paragraph = Paragraph::new(text)
+                    .style(active_style)
+                    .alignment(Alignment::Left)
+                    .wrap(Wrap { trim: false })
+                    .block(active_block.clone().title("Remove ban?"));
+                let area = centered_rect(40, 30, Rect::new(0, 0, 40, 7), size);
+
+                f.render_widget(Clear, area);
+                f.render_widget(paragraph, area);
+            }
         },
-        Screen::Exit => {
+        Screen::Exit(_) => {
             const EXIT_TEXT: &str = "Are you sure you want to quit?\n\nPress Enter to quit\nPress Escape to cancel";
             let paragraph = Paragraph::new(EXIT_TEXT)
                 .style(active_style)
@@ -364,6 +1509,286 @@ pub fn process_app<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
 }
 
+const PLAYERS_WIDTH: u16 = 64;
+const CONNECTIONS_WIDTH: u16 = 28;
+
+// a row of the Connections list
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TreeItem {
+    // the merged timeline of every connection
+    All,
+    Group(String),
+    Connection(u8)
+}
+
+// the "All" timeline once there is something to merge, ungrouped connections, then each group by name with its members unless folded
+fn connection_tree(econ_tabs: &VecDeque<EconTab>, collapsed: &BTreeSet<String>) -> Vec<TreeItem> {
+    let mut groups: BTreeMap<&str, Vec<u8>> = BTreeMap::new();
+    for (i, econ_tab) in econ_tabs.iter().enumerate() {
+        groups.entry(econ_tab.group.as_str()).or_default().push(i as u8);
+    }
+
+    let mut items = Vec::new();
+    if econ_tabs.len() > 1 {
+        items.push(TreeItem::All);
+    }
+    for (group, members) in groups {
+        if !group.is_empty() {
+            items.push(TreeItem::Group(group.to_string()));
+            if collapsed.contains(group) {
+                continue;
+            }
+        }
+        items.extend(members.into_iter().map(TreeItem::Connection));
+    }
+
+    items
+}
+
+fn group_members(econ_tabs: &VecDeque<EconTab>, group: &str) -> Vec<u8> {
+    econ_tabs
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.group == group)
+        .map(|(i, _)| i as u8)
+        .collect()
+}
+
+// row of the cursor, a connection hidden in a folded group puts it on the group
+fn tree_cursor(tree: &[TreeItem], data: &Main, econ_tabs: &VecDeque<EconTab>) -> usize {
+    let position = |item: TreeItem| tree.iter().position(|i| *i == item);
+    let group = data.group.clone().or_else(|| econ_tabs.get(data.connection as usize).map(|t| t.group.clone()));
+
+    match &data.group {
+        _ if data.timeline => position(TreeItem::All),
+        Some(group) => position(TreeItem::Group(group.clone())),
+        None => position(TreeItem::Connection(data.connection))
+    }
+    .or_else(|| group.and_then(|group| position(TreeItem::Group(group))))
+    .unwrap_or(0)
+}
+
+fn select_tree_item(data: &mut Main, item: &TreeItem) {
+    data.timeline = *item == TreeItem::All;
+
+    match item {
+        TreeItem::All => data.group = None,
+        TreeItem::Group(group) => data.group = Some(group.clone()),
+        TreeItem::Connection(i) => {
+            data.group = None;
+            if data.connection != *i {
+                data.player = 0;
+            }
+            data.connection = *i;
+        }
+    }
+}
+
+fn players_table(econ_tab: Option<&EconTab>, style: Style, block: Block<'static>) -> Table<'static> {
+    let rows: Vec<Row> = econ_tab
+        .map(|t| t.players.values().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| {
+            let team = match p.team {
+                Some(-1) => "spec".to_string(),
+                Some(team) => team.to_string(),
+                None => String::new()
+            };
+            Row::new(vec![
+                Cell::from(p.id.to_string()),
+                Cell::from(p.name.clone()),
+                Cell::from(p.clan.clone()),
+                Cell::from(p.score.map(|s| s.to_string()).unwrap_or_default()),
+                Cell::from(team),
+                Cell::from(p.auth.clone().unwrap_or_default()),
+                Cell::from(p.address.clone())
+            ])
+        })
+        .collect();
+
+    let title = format!("Players ({})", rows.len());
+
+    Table::new(rows)
+        .header(Row::new(vec!["ID", "Name", "Clan", "Score", "Team", "Auth", "Address"]).style(style.add_modifier(Modifier::BOLD)))
+        .widths(&[
+            Constraint::Length(3),
+            Constraint::Length(16),
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Length(4),
+            Constraint::Length(6),
+            Constraint::Min(10)
+        ])
+        .style(style)
+        .block(block.title(title))
+}
+
+fn bans_table(econ_tab: Option<&EconTab>, style: Style, block: Block<'static>) -> Table<'static> {
+    let (name, rows): (String, Vec<Row>) = match econ_tab {
+        Some(econ_tab) => {
+            let rows = econ_tab.bans
+                .iter()
+                .map(|b| {
+                    let remaining = match econ_tab.ban_remaining(b) {
+                        Some(1) => "1 minute".to_string(),
+                        Some(minutes) => format!("{} minutes", minutes),
+                        None => "forever".to_string()
+                    };
+                    Row::new(vec![
+                        Cell::from(b.index.to_string()),
+                        Cell::from(b.target()),
+                        Cell::from(remaining),
+                        Cell::from(b.reason.clone())
+                    ])
+                })
+                .collect();
+            (econ_tab.name.clone(), rows)
+        },
+        None => (String::new(), Vec::new())
+    };
+
+    let title = format!("Bans - {} ({})", name, rows.len());
+
+    Table::new(rows)
+        .header(Row::new(vec!["#", "Address", "Remaining", "Reason"]).style(style.add_modifier(Modifier::BOLD)))
+        .widths(&[
+            Constraint::Length(4),
+            Constraint::Length(34),
+            Constraint::Length(14),
+            Constraint::Min(10)
+        ])
+        .style(style)
+        .block(block.title(title))
+}
+
+// the lines of `econ_tab` that fit into `area`, the scroll offset hiding the overflow at the top and the title
+fn console_view(app: &App, econ_tab: &EconTab, area: Rect) -> (Vec<Spans<'static>>, u16, String) {
+    let gutter = |l: &Line| if app.config.show_timestamps {
+        format!("{} ", app.config.timestamp_format.format(&l.received_at, l.received_instant))
+    }
+    else {
+        String::new()
+    };
+    let note = |l: &Line| match l.group.filter(|g| l.event == Event::Sent && econ_tab.collapsed.contains(g)) {
+        Some(group) => format!(" (+{} lines)", econ_tab.hidden_replies(group)),
+        None => String::new()
+    };
+    let (lines, offset) = econ_tab.visible(
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+        |l| format!("{}{}{}", gutter(l), l.raw, note(l))
+    );
+    let query = econ_tab.find.as_ref().map(|find| find.query.value()).unwrap_or_default();
+    let current = econ_tab.find_current();
+    let selected = econ_tab.selected_command();
+    let marked = econ_tab.marked_range();
+    let content = lines
+        .iter()
+        .map(|(received, l)| {
+            let mut style = app.palette.style(l);
+            if selected.is_some_and(|s| std::ptr::eq(s, *l)) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            if marked.as_ref().is_some_and(|range| range.contains(received)) {
+                style = style.bg(Color::LightCyan);
+            }
+            let mut spans = highlight(&l.raw, style, query, current.is_some_and(|c| std::ptr::eq(c, *l)));
+            let (gutter, note) = (gutter(l), note(l));
+            if !gutter.is_empty() {
+                spans.0.insert(0, Span::styled(gutter, Style::default().fg(Color::DarkGray)));
+            }
+            if !note.is_empty() {
+                spans.0.push(Span::styled(note, style.add_modifier(Modifier::ITALIC)));
+            }
+            spans
+        })
+        .collect();
+
+    let mut title = format!("Console - {}", econ_tab.name);
+    if !econ_tab.following() {
+        title = format!("{} (+{} below, End to follow)", title, econ_tab.scroll);
+    }
+    if econ_tab.mark.is_some() {
+        title.push_str(" - marking, e: export, v: clear");
+    }
+
+    (content, offset, title)
+}
+
+// the merged timeline, each line behind the coloured name of its connection
+fn timeline_view(app: &App, area: Rect) -> (Vec<Spans<'static>>, u16, String) {
+    let gutter = |l: &Line| if app.config.show_timestamps {
+        format!("{} ", app.config.timestamp_format.format(&l.received_at, l.received_instant))
+    }
+    else {
+        String::new()
+    };
+    let label = |entry: &timeline::Entry| format!("[{}] ", app.econ_tabs[entry.tab].name);
+    let (entries, offset) = app.timeline.visible(
+        &app.econ_tabs,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+        |entry| format!("{}{}{}", gutter(entry.line), label(entry), entry.line.raw)
+    );
+    let content = entries
+        .iter()
+        .map(|entry| {
+            let mut spans = Vec::new();
+            let gutter = gutter(entry.line);
+            if !gutter.is_empty() {
+                spans.push(Span::styled(gutter, Style::default().fg(Color::DarkGray)));
+            }
+            spans.push(Span::styled(label(entry), Style::default().fg(timeline::label_color(entry.tab)).add_modifier(Modifier::BOLD)));
+            spans.push(Span::styled(entry.line.raw.clone(), app.palette.style(entry.line)));
+            Spans::from(spans)
+        })
+        .collect();
+
+    let mut title = String::from("Console - All");
+    if !app.timeline.following() {
+        title.push_str(" (scrolled back, End to follow)");
+    }
+
+    (content, offset, title)
+}
+
+// draws the line in its colour rule's `base` style and marks every occurrence of `query`,
+// the current match stands out from the rest
+fn highlight(line: &str, base: Style, query: &str, current: bool) -> Spans<'static> {
+    let style = if current {
+        Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD)
+    }
+    else {
+        Style::default().bg(Color::Yellow).fg(Color::Black)
+    };
+    let mut spans = Vec::new();
+    let mut end = 0;
+
+    for range in find::occurrences(line, query) {
+        spans.push(Span::styled(line[end..range.start].to_string(), base));
+        spans.push(Span::styled(line[range.clone()].to_string(), style));
+        end = range.end;
+    }
+    spans.push(Span::styled(line[end..].to_string(), base));
+
+    Spans::from(spans)
+}
+
+fn state_color(state: &ConnectionState) -> Color {
+    match state {
+        ConnectionState::Connecting | ConnectionState::Authenticating => Color::Yellow,
+        ConnectionState::Authenticated => Color::Green,
+        ConnectionState::AuthFailed => Color::Red,
+        ConnectionState::Disconnected => Color::DarkGray,
+        ConnectionState::Reconnecting(_) => Color::LightRed
+    }
+}
+
+fn profiles_warning(error: &str) -> String {
+    format!("profiles.toml could not be loaded and will not be saved: {}", error)
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, min_r: Rect, base_r: Rect) -> Rect {
     let min_percent_x = ((min_r.width.min(base_r.width) as f32) / (base_r.width as f32) * 100f32) as u16;
     let min_percent_y = ((min_r.height.min(base_r.height) as f32) / (base_r.height as f32) * 100f32) as u16;
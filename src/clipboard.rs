@@ -0,0 +1,32 @@
+use std::io::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// asks the terminal to put `text` on the system clipboard (OSC 52), this also works over ssh
+// but some terminals ignore it unless allowed in their settings
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    write!(stdout, "\x1b]52;c;{}\x07", encode(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            }
+            else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
@@ -0,0 +1,133 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tui::style::{Color, Modifier, Style};
+
+use crate::parser::Line;
+
+// one [[color]] entry of config.toml. every condition that is set has to match,
+// the first matching rule styles the line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorRule {
+    // parsed event kind, see `Event::kind`
+    pub event: Option<String>,
+    // tag in front of the line like chat, server or game
+    pub system: Option<String>,
+    pub regex: Option<String>,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool
+}
+
+impl ColorRule {
+    fn event(event: &str, fg: &str, bold: bool) -> Self {
+        Self { event: Some(event.to_string()), fg: Some(fg.to_string()), bold, ..Default::default() }
+    }
+
+    // tuned for the white background the ui draws on
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::event("sent", "magenta", true),
+            Self::event("chat", "black", false),
+            Self::event("teamchat", "blue", false),
+            Self::event("server_chat", "black", true),
+            Self::event("kill", "red", false),
+            Self::event("join", "green", false),
+            Self::event("leave", "green", false),
+            Self::event("team", "green", false),
+            Self::event("vote", "magenta", false),
+            Self::event("map", "cyan", true),
+            Self { regex: Some("(?i)error|failed".to_string()), fg: Some("lightred".to_string()), bold: true, ..Default::default() }
+        ]
+    }
+}
+
+struct CompiledRule {
+    event: Option<String>,
+    system: Option<String>,
+    regex: Option<Regex>,
+    style: Style
+}
+
+pub struct Palette {
+    rules: Vec<CompiledRule>
+}
+
+impl Palette {
+    // rules with an invalid regex are dropped, unknown colour names are ignored
+    pub fn new(rules: &[ColorRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                let regex = match &rule.regex {
+                    Some(regex) => Some(Regex::new(regex).ok()?),
+                    None => None
+                };
+
+                let mut style = Style::default();
+                if let Some(fg) = rule.fg.as_deref().and_then(parse_color) {
+                    style = style.fg(fg);
+                }
+                if let Some(bg) = rule.bg.as_deref().and_then(parse_color) {
+                    style = style.bg(bg);
+                }
+                if rule.bold {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+
+                Some(CompiledRule {
+                    event: rule.event.as_ref().map(|e| e.to_lowercase()),
+                    system: rule.system.as_ref().map(|s| s.to_lowercase()),
+                    regex,
+                    style
+                })
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    pub fn style(&self, line: &Line) -> Style {
+        let kind = line.event.kind();
+        let system = line.system.as_deref().map(|s| s.to_lowercase());
+
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.event.as_ref().is_none_or(|event| event == kind)
+                    && rule.system.as_ref().is_none_or(|s| Some(s) == system.as_ref())
+                    && rule.regex.as_ref().is_none_or(|regex| regex.is_match(&line.raw))
+            })
+            .map_or(Style::default(), |rule| rule.style)
+    }
+}
+
+// tui colour names like "red" or "lightblue", or "#rrggbb"
+fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8));
+    }
+
+    let color = match name.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None
+    };
+
+    Some(color)
+}
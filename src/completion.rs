@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use tui_input::Input;
+
+use crate::parser::PlayerInfo;
+
+// commands and config variables of vanilla teeworlds and ddnet servers
+pub const COMMANDS: &[&str] = &[
+    "add_map_votes", "add_vote", "ban", "ban_range", "bans", "bans_save", "broadcast", "change_map",
+    "clear_votes", "dbg_dummies", "dump_sqlservers", "echo", "exec", "force_pause", "force_teambalance",
+    "force_vote", "freezehammer", "kick", "left", "list_vote", "logout", "mod_command", "move", "move_raw",
+    "mute", "mutes", "muteid", "muteip", "pause", "pause_game", "random_map", "random_unfinished_map",
+    "record", "reload", "remove_vote", "restart", "right", "say", "set_team", "set_team_all",
+    "set_team_ddr", "shutdown", "shuffle_teams", "sound", "stoprecord", "status", "super", "swap_teams",
+    "team", "tele", "timeout_cache", "totele", "tune", "tune_dump", "tune_reset", "tune_zone",
+    "tune_zone_dump", "tune_zone_reset", "unban", "unban_all", "unban_range", "undeep", "unmute",
+    "unsolo", "unsuper", "up", "down", "vote", "vote_delay", "vote_mute", "vote_unmute", "vote_mutes",
+    "whitelist_add", "whitelist_remove",
+    "access_level", "access_status",
+    "bindaddr", "conn_timeout", "dbg_stress", "ec_auth_timeout", "ec_bantime", "ec_bindaddr",
+    "ec_output_level", "ec_password", "ec_port", "logfile", "loglevel", "password", "sv_auto_demo_max",
+    "sv_auto_demo_record", "sv_auto_record", "sv_ban_default_time", "sv_broadcast", "sv_chat_delay",
+    "sv_deepfly", "sv_destroy_bullets_on_death", "sv_destroy_lasers_on_death", "sv_dnsbl",
+    "sv_dnsbl_ban", "sv_dnsbl_chat", "sv_dnsbl_host", "sv_dnsbl_key", "sv_dnsbl_vote", "sv_endless_drag",
+    "sv_external_port", "sv_freeze_delay", "sv_gametype", "sv_high_bandwidth", "sv_hit", "sv_inactivekick",
+    "sv_inactivekick_spec", "sv_inactivekick_time", "sv_map", "sv_maprotation", "sv_matches_per_map",
+    "sv_max_clients", "sv_max_clients_per_ip", "sv_motd", "sv_name", "sv_old_laser", "sv_pauseable",
+    "sv_player_slots", "sv_port", "sv_powerups", "sv_rcon_bantime", "sv_rcon_helper_password",
+    "sv_rcon_max_tries", "sv_rcon_mod_password", "sv_rcon_password", "sv_register", "sv_reserved_slots",
+    "sv_reserved_slots_pass", "sv_respawn_delay_tdm", "sv_rounds_per_map", "sv_scorelimit",
+    "sv_show_others", "sv_show_others_default", "sv_skill_level", "sv_solo_server", "sv_spamprotection",
+    "sv_spectator_slots", "sv_strict_spectate_mode", "sv_team", "sv_teambalance_time", "sv_teamdamage",
+    "sv_tournament_mode", "sv_timelimit", "sv_vote_kick", "sv_vote_kick_bantime", "sv_vote_kick_min",
+    "sv_vote_majority", "sv_vote_map", "sv_vote_spectate", "sv_vote_spectate_rejoindelay", "sv_vote_time",
+    "sv_warmup", "sv_welcome",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub value: String,
+    pub label: String
+}
+
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub candidates: Vec<Candidate>,
+    pub selected: Option<usize>,
+    // char range of the word being completed, `end` moves as candidates are cycled
+    start: usize,
+    end: usize
+}
+
+impl Completion {
+    // fills in the longest common prefix, or the whole candidate when only one matches.
+    // returns the popup state when the user still has to choose
+    pub fn start(input: &mut Input, players: &BTreeMap<u8, PlayerInfo>) -> Option<Self> {
+        let value: Vec<char> = input.value().chars().collect();
+        let end = input.cursor();
+        let start = value[..end].iter().rposition(|c| c.is_whitespace()).map_or(0, |i| i + 1);
+        let word: String = value[start..end].iter().collect();
+
+        let candidates = if start == 0 {
+            commands(&word)
+        }
+        else {
+            arguments(&word, players)
+        };
+
+        match candidates.len() {
+            0 => None,
+            1 => {
+                replace(input, start, end, &format!("{} ", candidates[0].value));
+                None
+            },
+            _ => {
+                let prefix = common_prefix(&candidates);
+                let end = replace(input, start, end, &prefix);
+
+                Some(Self { candidates, selected: None, start, end })
+            }
+        }
+    }
+
+    pub fn cycle(&mut self, input: &mut Input, backwards: bool) {
+        let count = self.candidates.len();
+        let selected = match (self.selected, backwards) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count
+        };
+
+        self.selected = Some(selected);
+        self.end = replace(input, self.start, self.end, &self.candidates[selected].value);
+    }
+}
+
+fn commands(word: &str) -> Vec<Candidate> {
+    let mut commands: Vec<&str> = COMMANDS.iter().copied().filter(|c| c.starts_with(word)).collect();
+    commands.sort_unstable();
+
+    commands
+        .into_iter()
+        .map(|c| Candidate { value: c.to_string(), label: c.to_string() })
+        .collect()
+}
+
+fn arguments(word: &str, players: &BTreeMap<u8, PlayerInfo>) -> Vec<Candidate> {
+    let lower = word.to_lowercase();
+    let mut candidates = Vec::new();
+
+    for (id, PlayerInfo { name, .. }) in players {
+        if id.to_string().starts_with(word) {
+            candidates.push(Candidate { value: id.to_string(), label: format!("{}: {}", id, name) });
+        }
+        else if name.to_lowercase().starts_with(&lower) {
+            candidates.push(Candidate { value: name.clone(), label: format!("{}: {}", id, name) });
+        }
+    }
+
+    candidates
+}
+
+fn common_prefix(candidates: &[Candidate]) -> String {
+    let first = &candidates[0].value;
+    let mut len = first.chars().count();
+
+    for candidate in &candidates[1..] {
+        len = first
+            .chars()
+            .zip(candidate.value.chars())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count();
+    }
+
+    first.chars().take(len).collect()
+}
+
+// replaces the chars in start..end, returns the new end
+fn replace(input: &mut Input, start: usize, end: usize, text: &str) -> usize {
+    let value: Vec<char> = input.value().chars().collect();
+    let before: String = value[..start].iter().collect();
+    let after: String = value[end.min(value.len())..].iter().collect();
+    let cursor = start + text.chars().count();
+
+    *input = Input::new(format!("{}{}{}", before, text, after)).with_cursor(cursor);
+
+    cursor
+}
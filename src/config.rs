@@ -0,0 +1,118 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use chrono::{DateTime, Local};
+
+use serde::{Deserialize, Serialize};
+
+use crate::colors::ColorRule;
+
+// $XDG_CONFIG_HOME/tw-econ-tui on linux
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("tw-econ-tui"))
+}
+
+// $XDG_DATA_HOME/tw-econ-tui on linux
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("tw-econ-tui"))
+}
+
+// connection names as they can appear in file names. a name that had to change gets a hash of
+// the original so "a b" and "a_b" stay apart
+pub fn file_name(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    if safe == name {
+        return safe;
+    }
+
+    // fnv-1a, stable across builds unlike the std hasher
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+
+    format!("{}-{:08x}", safe, hash)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub scrollback_lines: usize,
+    // how often the player list is refreshed with a background `status`
+    pub status_interval_secs: u64,
+    // receive time gutter in the Console, toggled with t and cycled with T
+    pub show_timestamps: bool,
+    pub timestamp_format: TimestampFormat,
+    // session logs start a new file once they reach this size
+    pub log_max_bytes: u64,
+    // console colours, any [[color]] in the file replaces the built in set
+    #[serde(rename = "color")]
+    pub colors: Vec<ColorRule>
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scrollback_lines: 10_000,
+            status_interval_secs: 10,
+            show_timestamps: false,
+            timestamp_format: TimestampFormat::Clock,
+            log_max_bytes: 10 * 1024 * 1024,
+            colors: ColorRule::defaults()
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    pub fn load() -> Self {
+        let content = match Self::path().map(fs::read_to_string) {
+            Some(Ok(content)) => content,
+            _ => return Self::default()
+        };
+
+        toml::from_str(&content).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    // 5s, 3m, 2h ago
+    Relative,
+    // HH:MM:SS
+    Clock,
+    // ISO-8601 with milliseconds and offset
+    Iso
+}
+
+impl TimestampFormat {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Relative => Self::Clock,
+            Self::Clock => Self::Iso,
+            Self::Iso => Self::Relative
+        }
+    }
+
+    // every format pads to a fixed width so the console text stays aligned
+    pub fn format(&self, wall: &DateTime<Local>, monotonic: Instant) -> String {
+        match self {
+            Self::Relative => {
+                let secs = monotonic.elapsed().as_secs();
+                let ago = match secs {
+                    0..=59 => format!("{}s", secs),
+                    60..=3599 => format!("{}m", secs / 60),
+                    3600..=86399 => format!("{}h", secs / 3600),
+                    _ => format!("{}d", secs / 86400)
+                };
+                format!("{:>4} ago", ago)
+            },
+            Self::Clock => wall.format("%H:%M:%S").to_string(),
+            Self::Iso => wall.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()
+        }
+    }
+}
@@ -1,11 +1,701 @@
+use std::{collections::{BTreeMap, BTreeSet, VecDeque}, io, iter, mem, net::SocketAddr, ops::RangeInclusive, time::{Duration, Instant}};
+
+use tui_input::Input;
+use unicode_width::UnicodeWidthChar;
 use tw_econ::connection::Connection;
 
+use crate::{address::Lookup, completion::Completion, export::ExportScope, filter::Filter, find::{self, Find}, history::History, parser::{self, BanEntry, Event, Line, PlayerInfo}, scrollback::Scrollback, sessionlog::SessionLog};
+
 pub type EconId = u8;
 
+// how long rows of our own `status` keep arriving after it was sent
+const STATUS_REPLY_WINDOW: Duration = Duration::from_secs(2);
+// console output (not chat or game events) this soon after a command is grouped under it
+const COMMAND_REPLY_WINDOW: Duration = Duration::from_secs(2);
+
 pub struct EconTab {
     pub name: String,
+    pub group: String,
+    // the address as entered, looked up again before every reconnect
+    pub host: String,
+    // what `host` resolved to last, None until the first lookup succeeded
+    pub address: Option<SocketAddr>,
+    pub lookup: Option<Lookup>,
+    pub password: String,
     pub connection: Connection<2048, 1>,
-    pub messages: Vec<String>,
-    pub buffer: String,
-    pub scroll: u16
+    pub state: ConnectionState,
+    // reconnects since the last login, a server that drops us right after accepting keeps getting slower retries
+    pub attempts: u32,
+    pub messages: Scrollback<Line>,
+    pub buffer: Input,
+    pub history: History,
+    pub completion: Option<Completion>,
+    pub players: BTreeMap<u8, PlayerInfo>,
+    // set while the reply to our periodic `status` is arriving, its rows update `players` without reaching the console
+    pub status_poll: Option<StatusPoll>,
+    pub last_status: Instant,
+    pub bans: Vec<BanEntry>,
+    // set from our own `bans` until its "N bans" footer arrives, the listing stays out of the console
+    pub ban_poll: Option<Instant>,
+    // when `bans` was last answered, remaining minutes count down from here
+    pub bans_fetched: Option<Instant>,
+    // lines ever added to `messages`, survives the scrollback dropping old ones
+    pub received: u64,
+    pub filter: Filter,
+    // `received` numbers of the lines that pass the filter and are not folded away, oldest first
+    pub shown: VecDeque<u64>,
+    pub log: Option<SessionLog>,
+    // one end of the range for exporting, the other is the bottom of the console
+    pub mark: Option<u64>,
+    pub find: Option<Find>,
+    // the command that output is currently grouped under, with when it was sent
+    pub reply_group: Option<(u64, Instant)>,
+    // commands whose replies are folded away
+    pub collapsed: BTreeSet<u64>,
+    // lines in the scrollback grouped under each command, the command included
+    pub group_lengths: BTreeMap<u64, usize>,
+    // the command picked with [ and ] in the Console
+    pub selected_command: Option<u64>,
+    // shown lines between the bottom of the console and the newest one, 0 follows new output
+    pub scroll: usize
+}
+
+impl EconTab {
+    pub fn new(name: String, host: String, address: Option<SocketAddr>, password: String, scrollback_lines: usize) -> Self {
+        let history = History::load(&name);
+
+        Self {
+            name,
+            group: String::new(),
+            host,
+            address,
+            lookup: None,
+            password,
+            connection: Connection::new(),
+            state: ConnectionState::Connecting,
+            attempts: 0,
+            messages: Scrollback::new(scrollback_lines),
+            buffer: Input::default(),
+            history,
+            completion: None,
+            players: BTreeMap::new(),
+            status_poll: None,
+            last_status: Instant::now(),
+            bans: Vec::new(),
+            ban_poll: None,
+            bans_fetched: None,
+            received: 0,
+            filter: Filter::new(),
+            shown: VecDeque::new(),
+            log: None,
+            mark: None,
+            find: None,
+            reply_group: None,
+            collapsed: BTreeSet::new(),
+            group_lengths: BTreeMap::new(),
+            selected_command: None,
+            scroll: 0
+        }
+    }
+
+    pub fn launch(&mut self) -> io::Result<()> {
+        self.connection = Connection::new();
+        self.state = ConnectionState::Connecting;
+        self.players.clear();
+        self.status_poll = None;
+        self.ban_poll = None;
+
+        let address = self.address.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host not resolved yet"))?;
+
+        self.connection.launch_with_password(address, self.password.clone())?;
+
+        self.state = ConnectionState::Authenticating;
+
+        Ok(())
+    }
+
+    // keeps the tab and its scrollback, on_tick leaves it alone until launched again
+    pub fn disconnect(&mut self) {
+        self.connection = Connection::new();
+        self.state = ConnectionState::Disconnected;
+        self.lookup = None;
+        self.status_poll = None;
+        self.ban_poll = None;
+    }
+
+    // the command is echoed only once it went out, a failure is noted in the console instead
+    pub fn send(&mut self, command: String) -> io::Result<()> {
+        let result = match self.state {
+            ConnectionState::Authenticated => self.connection.send(command.clone()),
+            _ => Err(io::Error::new(io::ErrorKind::NotConnected, "not connected"))
+        };
+
+        match &result {
+            Ok(()) => {
+                // whatever arrives now belongs to the user's command
+                self.status_poll = None;
+                self.push(Line::sent(&command));
+            },
+            Err(error) => self.notice(format!("could not send '{}': {}", command, error))
+        }
+
+        result
+    }
+
+    pub fn request_status(&mut self) {
+        self.last_status = Instant::now();
+
+        if self.connection.send("status".to_string()).is_ok() {
+            self.status_poll = Some(StatusPoll { sent: Instant::now(), seen: BTreeSet::new() });
+        }
+    }
+
+    pub fn request_bans(&mut self) {
+        if self.connection.send("bans".to_string()).is_ok() {
+            self.ban_poll = Some(Instant::now());
+        }
+    }
+
+    // 1 connection = 1 ms
+    pub fn poll(&mut self) {
+        if let Some(status_poll) = self.status_poll.take_if(|p| p.sent.elapsed() >= STATUS_REPLY_WINDOW) {
+            // whoever was not listed has left without us seeing the leave line. no rows at all
+            // means the reply got lost or is late, not that the server emptied
+            if !status_poll.seen.is_empty() {
+                self.players.retain(|id, _| status_poll.seen.contains(id));
+            }
+        }
+        // a server without the footer would hide net_ban lines forever
+        self.ban_poll.take_if(|sent| sent.elapsed() >= STATUS_REPLY_WINDOW);
+
+        if let Ok(messages) = self.connection.recv() {
+            let messages = messages
+                .lines()
+                .filter(|s| !s.is_empty())
+                .map(|s| parser::parse(s.to_string()))
+                .collect::<Vec<Line>>();
+            for message in &messages {
+                self.on_line(message);
+            }
+            for message in messages {
+                if self.status_poll.is_some() && matches!(message.event, Event::Status(_)) {
+                    continue;
+                }
+                if self.ban_poll.is_some() && matches!(message.event, Event::Ban(_)) {
+                    continue;
+                }
+                if let (Some(_), Event::BanCount(_)) = (self.ban_poll, &message.event) {
+                    self.ban_poll = None;
+                    continue;
+                }
+                self.push(message);
+            }
+        }
+    }
+
+    fn push(&mut self, mut line: Line) {
+        if line.event == Event::Sent {
+            self.reply_group = Some((self.received, Instant::now()));
+        }
+        line.group = self.reply_group
+            .filter(|(_, sent)| sent.elapsed() < COMMAND_REPLY_WINDOW)
+            .filter(|_| line.event == Event::Sent || line.event.can_reply())
+            .map(|(group, _)| group);
+
+        if let Some(group) = line.group {
+            *self.group_lengths.entry(group).or_default() += 1;
+        }
+
+        let log_error = self.log.as_mut().and_then(|log| log.write(&line).err());
+
+        let shown = self.is_shown(&line);
+
+        // keep the view still while the user is reading older output
+        if self.scroll > 0 && shown {
+            self.scroll += 1;
+        }
+        if shown {
+            self.shown.push_back(self.received);
+            if let Some(find) = self.find.as_mut().filter(|find| !find::occurrences(&line.raw, find.query.value()).is_empty()) {
+                find.matches.push_back(self.received);
+            }
+        }
+        if let Some(group) = self.messages.push(line).and_then(|dropped| dropped.group) {
+            if let Some(length) = self.group_lengths.get_mut(&group) {
+                *length -= 1;
+                if *length == 0 {
+                    self.group_lengths.remove(&group);
+                }
+            }
+        }
+        self.received += 1;
+
+        // a full scrollback drops a line for every new one, which may have been a shown one
+        let first = self.first_received();
+        while self.shown.front().is_some_and(|&received| received < first) {
+            self.shown.pop_front();
+        }
+        if let Some(find) = &mut self.find {
+            while find.matches.front().is_some_and(|&received| received < first) {
+                find.matches.pop_front();
+            }
+        }
+        self.scroll = self.scroll.min(self.shown.len().saturating_sub(1));
+
+        if let Some(error) = log_error {
+            // one notice instead of an error for every following line
+            self.log = None;
+            self.notice(format!("session log stopped: {}", error));
+        }
+    }
+
+    // a line of our own in the console, for errors that belong to this tab
+    pub fn notice(&mut self, text: String) {
+        self.push(parser::parse(text));
+    }
+
+    pub fn on_line(&mut self, line: &Line) {
+        match &line.event {
+            Event::EconAuth { success } if self.state == ConnectionState::Authenticating => {
+                self.state = if *success { ConnectionState::Authenticated } else { ConnectionState::AuthFailed };
+                if *success {
+                    self.attempts = 0;
+                }
+            },
+            Event::Leave { player } => {
+                self.players.remove(&player.id);
+            },
+            Event::Status(info) => {
+                if let Some(status_poll) = &mut self.status_poll {
+                    status_poll.seen.insert(info.id);
+                }
+                self.players.insert(info.id, info.clone());
+            },
+            Event::Join { player } => {
+                let info = self.players.entry(player.id).or_insert_with(|| PlayerInfo { id: player.id, ..Default::default() });
+                if !player.name.is_empty() {
+                    info.name = player.name.clone();
+                }
+            },
+            Event::TeamChange { player, team } => {
+                let info = self.players.entry(player.id).or_insert_with(|| PlayerInfo { id: player.id, ..Default::default() });
+                info.name = player.name.clone();
+                info.team = Some(*team);
+            },
+            Event::Ban(ban) => {
+                // every listing starts over at #0
+                if ban.index == 0 {
+                    self.bans.clear();
+                }
+                self.bans.push(ban.clone());
+            },
+            Event::BanCount(count) => {
+                self.bans.truncate(*count);
+                self.bans_fetched = Some(Instant::now());
+            },
+            Event::RconAuth { id, level } => {
+                if let Some(info) = self.players.get_mut(id) {
+                    info.auth = Some(level.clone());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // lines that arrived after `received` had the given value, as far as the scrollback still holds them
+    pub fn since(&self, received: u64) -> impl Iterator<Item = &Line> {
+        let count = (self.received.saturating_sub(received) as usize).min(self.messages.len());
+
+        self.messages.range(self.messages.len() - count..self.messages.len())
+    }
+
+    // minutes left on a ban as of now, None for permanent ones
+    pub fn ban_remaining(&self, ban: &BanEntry) -> Option<u32> {
+        let elapsed = self.bans_fetched.map_or(0, |fetched| fetched.elapsed().as_secs() / 60) as u32;
+
+        ban.minutes.map(|minutes| minutes.saturating_sub(elapsed).max(1))
+    }
+
+    pub fn following(&self) -> bool {
+        self.scroll == 0
+    }
+
+    // passes the filter and is not the reply of a collapsed command
+    fn is_shown(&self, line: &Line) -> bool {
+        let folded = line.event != Event::Sent && line.group.is_some_and(|group| self.collapsed.contains(&group));
+
+        !folded && self.filter.matches(line)
+    }
+
+    pub fn shown(&self) -> Vec<&Line> {
+        self.shown.iter().filter_map(|&received| self.line(received)).collect()
+    }
+
+    // call after changing the filter, the console jumps back to the newest line
+    pub fn refilter(&mut self) {
+        self.filter.update();
+        self.reshow();
+        self.scroll = 0;
+    }
+
+    fn reshow(&mut self) {
+        let first = self.first_received();
+
+        self.shown = self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| self.is_shown(line))
+            .map(|(i, _)| first + i as u64)
+            .collect();
+
+        if let Some(find) = &self.find {
+            let matches = self.find_matches(find.query.value());
+            self.find.as_mut().unwrap().matches = matches;
+        }
+    }
+
+    fn line(&self, received: u64) -> Option<&Line> {
+        self.messages.get(received.checked_sub(self.first_received())? as usize)
+    }
+
+    // lines folded away with the command, as far as the scrollback still holds them
+    pub fn hidden_replies(&self, command: u64) -> usize {
+        self.group_lengths.get(&command).map_or(0, |length| length.saturating_sub(1))
+    }
+
+    // a sent command followed by everything grouped under it
+    pub fn command_group(&self, command: u64) -> Vec<&Line> {
+        self.messages.iter().filter(|line| line.group == Some(command)).collect()
+    }
+
+    pub fn selected_command(&self) -> Option<&Line> {
+        self.line(self.selected_command?)
+    }
+
+    // steps through the shown commands like n/N through search matches
+    pub fn select_command(&mut self, older: bool) {
+        let commands: Vec<u64> = self.shown
+            .iter()
+            .copied()
+            .filter(|&received| self.line(received).is_some_and(|line| line.event == Event::Sent))
+            .collect();
+
+        self.selected_command = self.step(&commands, self.selected_command, Some(older));
+    }
+
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(command) = self.selected_command {
+            if !self.collapsed.remove(&command) {
+                self.collapsed.insert(command);
+            }
+            self.reshow();
+            // the selected command itself stays shown, bring it back into view
+            self.scroll_to(command);
+        }
+    }
+
+    // what `received` was when messages[0] arrived
+    pub fn first_received(&self) -> u64 {
+        self.received - self.messages.len() as u64
+    }
+
+    // the shown lines that contain `query`, as `received` numbers
+    fn find_matches(&self, query: &str) -> VecDeque<u64> {
+        self.shown
+            .iter()
+            .copied()
+            .filter(|&received| self.line(received).is_some_and(|line| !find::occurrences(&line.raw, query).is_empty()))
+            .collect()
+    }
+
+    pub fn find_current(&self) -> Option<&Line> {
+        self.line(self.find.as_ref()?.current?)
+    }
+
+    // call after changing the search query, it starts over from where the console is scrolled
+    pub fn refind(&mut self) {
+        if let Some(find) = &self.find {
+            let matches = self.find_matches(find.query.value());
+            let find = self.find.as_mut().unwrap();
+            find.matches = matches;
+            find.current = None;
+        }
+
+        self.find_next(None);
+    }
+
+    pub fn find_next(&mut self, older: Option<bool>) {
+        let matches: Vec<u64> = self.find.iter().flat_map(|find| find.matches.iter().copied()).collect();
+        let current = self.find.as_ref().and_then(|find| find.current);
+        let next = self.step(&matches, current, older);
+
+        if let Some(find) = &mut self.find {
+            find.current = next;
+        }
+    }
+
+    // moves from `current` to the next older or newer of `targets` (shown `received` numbers, oldest first)
+    // and scrolls it to the bottom of the console. without a current one it picks the newest target
+    // that is on screen or above it
+    fn step(&mut self, targets: &[u64], current: Option<u64>, older: Option<bool>) -> Option<u64> {
+        let bottom = self.bottom().unwrap_or(0);
+
+        let next = *match (current, older) {
+            (Some(current), Some(true)) => targets.iter().rev().find(|&&t| t < current).or(targets.last()),
+            (Some(current), Some(false)) => targets.iter().find(|&&t| t > current).or(targets.first()),
+            _ => targets.iter().rev().find(|&&t| t <= bottom).or(targets.first())
+        }?;
+
+        self.scroll_to(next);
+
+        Some(next)
+    }
+
+    // puts a shown line at the bottom of the console
+    fn scroll_to(&mut self, received: u64) {
+        if let Ok(position) = self.shown.binary_search(&received) {
+            self.scroll = self.shown.len() - 1 - position;
+        }
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.shown.len().saturating_sub(1));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = self.shown.len().saturating_sub(1);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    // shown lines ending `scroll` lines above the newest one that fill `height` rows once `text`
+    // (the line as drawn) is wrapped to `width`, each with its `received` number, together with
+    // the row offset that hides whatever overflows at the top
+    pub fn visible(&self, width: u16, height: u16, text: impl Fn(&Line) -> String) -> (Vec<(u64, &Line)>, u16) {
+        let end = self.shown.len().saturating_sub(self.scroll);
+        let mut start = end;
+        let mut rows = 0;
+
+        while start > 0 && rows < height as usize {
+            start -= 1;
+            rows += self.line(self.shown[start]).map_or(1, |line| wrapped_rows(&text(line), width));
+        }
+
+        let lines = self.shown.range(start..end).filter_map(|&received| Some((received, self.line(received)?))).collect();
+
+        (lines, rows.saturating_sub(height as usize) as u16)
+    }
+
+    // the shown line at the bottom of the console
+    fn bottom(&self) -> Option<u64> {
+        let bottom = self.shown.len().checked_sub(self.scroll + 1)?;
+
+        self.shown.get(bottom).copied()
+    }
+
+    // drops a mark at the bottom line, the marked range runs from it to wherever the console is scrolled
+    pub fn toggle_mark(&mut self) {
+        self.mark = match self.mark {
+            Some(_) => None,
+            None => self.bottom()
+        };
+    }
+
+    pub fn marked_range(&self) -> Option<RangeInclusive<u64>> {
+        let (mark, bottom) = (self.mark?, self.bottom()?);
+
+        Some(mark.min(bottom)..=mark.max(bottom))
+    }
+
+    pub fn export_lines(&self, scope: ExportScope) -> Vec<&Line> {
+        match scope {
+            ExportScope::All => self.messages.iter().collect(),
+            ExportScope::Filtered => self.shown(),
+            ExportScope::Marked => {
+                let range = match self.marked_range() {
+                    Some(range) => range,
+                    None => return Vec::new()
+                };
+                self.shown
+                    .iter()
+                    .filter(|received| range.contains(received))
+                    .filter_map(|&received| self.line(received))
+                    .collect()
+            }
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.state {
+            ConnectionState::Reconnecting(backoff) => format!("{} (retry in {}s)", self.name, backoff.remaining().as_secs() + 1),
+            _ if self.log.is_some() => format!("{} (log)", self.name),
+            _ => self.name.clone()
+        }
+    }
+}
+
+// rows `text` takes in a Paragraph with `Wrap { trim: true }`, the same way tui's WordWrapper breaks it:
+// at the last word end that fits or mid-word if there is none, dropping whitespace at the start of a row
+pub fn wrapped_rows(text: &str, width: u16) -> usize {
+    const NBSP: char = '\u{a0}';
+
+    let width = width as usize;
+    let mut symbols = text.chars().chain(iter::once('\n'));
+    let mut next_row: Vec<(char, usize)> = Vec::new();
+    let mut rows = 0;
+
+    if width == 0 {
+        return 1;
+    }
+
+    loop {
+        let mut row = mem::take(&mut next_row);
+        let mut row_width: usize = row.iter().map(|(_, w)| w).sum();
+        let mut word_end = 0;
+        let mut prev_whitespace = false;
+
+        rows += 1;
+
+        for symbol in symbols.by_ref() {
+            let symbol_width = symbol.width().unwrap_or(0);
+            let whitespace = symbol.is_whitespace() && symbol != NBSP;
+
+            if symbol == '\n' {
+                return rows;
+            }
+            if symbol_width > width || (whitespace && row_width == 0) {
+                continue;
+            }
+            if whitespace && !prev_whitespace {
+                word_end = row.len();
+            }
+
+            row.push((symbol, symbol_width));
+            row_width += symbol_width;
+
+            if row_width > width {
+                let cut = if word_end != 0 { word_end } else { row.len() - 1 };
+                next_row.extend(row[cut..].iter().skip_while(|(c, _)| c.is_whitespace()));
+                break;
+            }
+
+            prev_whitespace = whitespace;
+        }
+    }
+}
+
+pub struct StatusPoll {
+    pub sent: Instant,
+    pub seen: BTreeSet<u8>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Authenticating,
+    Authenticated,
+    AuthFailed,
+    Disconnected,
+    Reconnecting(Backoff)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub attempts: u32,
+    pub next_attempt: Instant
+}
+
+impl Backoff {
+    const BASE_DELAY: Duration = Duration::from_secs(1);
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Self {
+        Self::after(0)
+    }
+
+    // picks up the delay where the attempts before it left off
+    pub fn after(attempts: u32) -> Self {
+        Self { attempts, next_attempt: Instant::now() + Self::delay(attempts) }
+    }
+
+    // the first attempt goes out on the next tick
+    pub fn now() -> Self {
+        Self { attempts: 0, next_attempt: Instant::now() }
+    }
+
+    pub fn ready(&self) -> bool {
+        Instant::now() >= self.next_attempt
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.next_attempt.saturating_duration_since(Instant::now())
+    }
+
+    pub fn fail(&mut self) {
+        self.attempts = self.attempts.saturating_add(1);
+        self.next_attempt = Instant::now() + Self::delay(self.attempts);
+    }
+
+    // 1s, 2s, 4s, ... capped at a minute
+    fn delay(attempts: u32) -> Duration {
+        Self::BASE_DELAY
+            .checked_mul(1 << attempts.min(6))
+            .unwrap_or(Self::MAX_DELAY)
+            .min(Self::MAX_DELAY)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the row counts tui 0.19 draws for each line, scrolling relies on them matching
+    #[test]
+    fn wrapped() {
+        let cases = [
+            ("", 10, 1),
+            // exactly as wide as the row
+            ("abcdefghij", 10, 1),
+            ("abcdefghijk", 10, 2),
+            ("hello world", 11, 1),
+            ("hello world", 10, 2),
+            // leading whitespace is dropped, trailing whitespace can spill into a row of its own
+            ("   hello", 5, 1),
+            ("  hello", 7, 1),
+            ("   ", 5, 1),
+            ("hello   ", 5, 2),
+            ("hello   ", 6, 2),
+            ("hello   world", 5, 2),
+            // a word longer than the row is broken mid-word
+            ("abcdefghijklmnopqrstuvwxy", 10, 3),
+            ("ab abcdefghijklmn", 10, 3),
+            // wide characters take two columns, one wider than the whole row is skipped
+            ("日本語のテキスト", 10, 2),
+            ("日本語の", 7, 2),
+            ("a日本", 2, 3),
+            ("ab日", 3, 2),
+            ("日", 1, 1),
+            // a non-breaking space keeps its words together
+            ("a\u{a0}b c", 3, 2),
+            ("aaaa\u{a0}bbbb", 5, 2),
+            ("aa\u{a0}bb cc", 5, 2),
+            ("\u{a0}\u{a0}ab", 2, 2)
+        ];
+
+        for (text, width, rows) in cases {
+            assert_eq!(wrapped_rows(text, width), rows, "{:?} in {} columns", text, width);
+        }
+    }
 }
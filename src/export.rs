@@ -0,0 +1,157 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use tui::style::{Color, Modifier, Style};
+
+use crate::{colors::Palette, parser::Line, record::Record};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    All,
+    // what the console filter lets through
+    Filtered,
+    // from the mark set with v to the bottom of the console
+    Marked
+}
+
+impl ExportScope {
+    pub fn next(self) -> Self {
+        match self {
+            Self::All => Self::Filtered,
+            Self::Filtered => Self::Marked,
+            Self::Marked => Self::All
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::All => "whole scrollback",
+            Self::Filtered => "filtered lines",
+            Self::Marked => "marked range"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Json,
+    Html
+}
+
+impl ExportFormat {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Text => Self::Json,
+            Self::Json => Self::Html,
+            Self::Html => Self::Text
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Html => "html"
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Json => "json",
+            Self::Html => "html"
+        }
+    }
+}
+
+// ~ stands for the home directory
+pub fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path)
+    }
+}
+
+pub fn write(path: &Path, format: ExportFormat, title: &str, lines: &[&Line], palette: &Palette) -> io::Result<()> {
+    let content = match format {
+        ExportFormat::Text => lines.iter().map(|l| format!("{}\n", l.raw)).collect(),
+        ExportFormat::Json => {
+            let records: Vec<Record> = lines.iter().map(|l| Record::new(l, None)).collect();
+            serde_json::to_string_pretty(&records)?
+        },
+        ExportFormat::Html => html(title, lines, palette)
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, content)
+}
+
+// the console's colour rules become inline styles
+fn html(title: &str, lines: &[&Line], palette: &Palette) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body style=\"background: #fff; color: #777\">\n<pre>\n",
+        escape(title)
+    );
+
+    for line in lines {
+        let style = css(palette.style(line));
+        if style.is_empty() {
+            html.push_str(&format!("{}\n", escape(&line.raw)));
+        }
+        else {
+            html.push_str(&format!("<span style=\"{}\">{}</span>\n", style, escape(&line.raw)));
+        }
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+
+    html
+}
+
+fn css(style: Style) -> String {
+    let mut css = Vec::new();
+
+    if let Some(fg) = style.fg.and_then(css_color) {
+        css.push(format!("color: {}", fg));
+    }
+    if let Some(bg) = style.bg.and_then(css_color) {
+        css.push(format!("background: {}", bg));
+    }
+    if style.add_modifier.contains(Modifier::BOLD) {
+        css.push("font-weight: bold".to_string());
+    }
+
+    css.join("; ")
+}
+
+fn css_color(color: Color) -> Option<String> {
+    let color = match color {
+        Color::Black => "#000000",
+        Color::Red => "#cd0000",
+        Color::Green => "#00cd00",
+        Color::Yellow => "#cdcd00",
+        Color::Blue => "#0000ee",
+        Color::Magenta => "#cd00cd",
+        Color::Cyan => "#00cdcd",
+        Color::Gray => "#e5e5e5",
+        Color::DarkGray => "#7f7f7f",
+        Color::LightRed => "#ff0000",
+        Color::LightGreen => "#00ff00",
+        Color::LightYellow => "#ffff00",
+        Color::LightBlue => "#5c5cff",
+        Color::LightMagenta => "#ff00ff",
+        Color::LightCyan => "#00ffff",
+        Color::White => "#ffffff",
+        Color::Rgb(r, g, b) => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        _ => return None
+    };
+
+    Some(color.to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,143 @@
+use regex::{Regex, RegexBuilder};
+use tui_input::Input;
+
+use crate::parser::{Event, Level, Line};
+
+// narrows what the Console shows, the scrollback itself is never touched
+pub struct Filter {
+    pub query: Input,
+    pub mode: FilterMode,
+    pub category: Category,
+    // off keeps the query and category around for the next time
+    pub enabled: bool,
+    regex: Option<Regex>,
+    pub error: Option<String>
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self {
+            query: Input::default(),
+            mode: FilterMode::Substring,
+            category: Category::All,
+            enabled: true,
+            regex: None,
+            error: None
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.enabled && (!self.query.value().is_empty() || self.category != Category::All)
+    }
+
+    // call after changing the query or the mode
+    pub fn update(&mut self) {
+        self.regex = None;
+        self.error = None;
+
+        if self.mode == FilterMode::Regex && !self.query.value().is_empty() {
+            match RegexBuilder::new(self.query.value()).case_insensitive(true).build() {
+                Ok(regex) => self.regex = Some(regex),
+                Err(error) => self.error = Some(error.to_string().lines().last().unwrap_or_default().to_string())
+            }
+        }
+    }
+
+    pub fn matches(&self, line: &Line) -> bool {
+        if !self.active() {
+            return true;
+        }
+
+        if !self.category.matches(line) {
+            return false;
+        }
+
+        let query = self.query.value();
+
+        match self.mode {
+            _ if query.is_empty() => true,
+            FilterMode::Substring => line.raw.to_lowercase().contains(&query.to_lowercase()),
+            // a broken pattern hides nothing until it is fixed
+            FilterMode::Regex => self.regex.as_ref().is_none_or(|regex| regex.is_match(&line.raw))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    Substring,
+    Regex
+}
+
+impl FilterMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Substring => Self::Regex,
+            Self::Regex => Self::Substring
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Substring => "text",
+            Self::Regex => "regex"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    All,
+    Chat,
+    Kills,
+    Players,
+    Votes,
+    Errors
+}
+
+impl Category {
+    pub fn next(self) -> Self {
+        match self {
+            Self::All => Self::Chat,
+            Self::Chat => Self::Kills,
+            Self::Kills => Self::Players,
+            Self::Players => Self::Votes,
+            Self::Votes => Self::Errors,
+            Self::Errors => Self::All
+        }
+    }
+
+    pub fn prev(&self) -> Self {
+        match self {
+            Self::All => Self::Errors,
+            Self::Chat => Self::All,
+            Self::Kills => Self::Chat,
+            Self::Players => Self::Kills,
+            Self::Votes => Self::Players,
+            Self::Errors => Self::Votes
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Chat => "chat",
+            Self::Kills => "kills",
+            Self::Players => "joins/leaves",
+            Self::Votes => "votes",
+            Self::Errors => "errors"
+        }
+    }
+
+    pub fn matches(&self, line: &Line) -> bool {
+        match self {
+            Self::All => true,
+            Self::Chat => matches!(line.event, Event::Chat { .. } | Event::TeamChat { .. } | Event::ServerChat { .. }),
+            Self::Kills => matches!(line.event, Event::Kill { .. }),
+            Self::Players => matches!(line.event, Event::Join { .. } | Event::Leave { .. } | Event::TeamChange { .. }),
+            Self::Votes => matches!(line.event, Event::VoteStarted { .. } | Event::VotePassed | Event::VoteFailed),
+            // only ddnet tags lines with a level, older servers just say so
+            Self::Errors => matches!(line.level, Some(Level::Error | Level::Warning)) || line.text.to_lowercase().contains("error")
+        }
+    }
+}
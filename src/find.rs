@@ -0,0 +1,41 @@
+use std::{collections::VecDeque, ops::Range};
+
+use tui_input::Input;
+
+// `/` search over a tab's console
+pub struct Find {
+    pub query: Input,
+    // the message the search is on, counted like `EconTab::received` so it survives new output
+    pub current: Option<u64>,
+    // the shown lines containing the query, as `received` numbers oldest first. EconTab keeps them up to date
+    pub matches: VecDeque<u64>
+}
+
+impl Find {
+    pub fn new() -> Self {
+        Self { query: Input::default(), current: None, matches: VecDeque::new() }
+    }
+}
+
+// byte ranges of `needle` in `haystack` ignoring ascii case, which keeps them on char boundaries
+pub fn occurrences(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    let mut found = Vec::new();
+    let mut start = 0;
+
+    if needle.is_empty() {
+        return found;
+    }
+
+    while start + needle.len() <= haystack.len() {
+        if haystack[start..start + needle.len()].eq_ignore_ascii_case(needle) {
+            found.push(start..start + needle.len());
+            start += needle.len();
+        }
+        else {
+            start += 1;
+        }
+    }
+
+    found
+}
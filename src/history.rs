@@ -0,0 +1,177 @@
+use std::{fs::{self, OpenOptions}, io::Write, path::PathBuf};
+
+use crate::config::{config_dir, file_name};
+
+const MAX_ENTRIES: usize = 1000;
+
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    // lines in the file, which is only appended to until it holds twice MAX_ENTRIES
+    file_lines: usize,
+    // index into entries while browsing with Up/Down
+    position: Option<usize>,
+    // what was typed before browsing started, restored when browsing past the newest entry
+    draft: String,
+    search: Option<Search>
+}
+
+pub struct Search {
+    pub query: String,
+    // index of the current match, searching continues from here towards older entries
+    pub found: Option<usize>
+}
+
+impl History {
+    pub fn path(name: &str) -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("history").join(format!("{}.history", file_name(name))))
+    }
+
+    pub fn load(name: &str) -> Self {
+        let path = Self::path(name);
+        let mut entries: Vec<String> = match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(content)) => content.lines().map(|s| s.to_string()).collect(),
+            _ => Vec::new()
+        };
+        let file_lines = entries.len();
+
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+
+        Self { entries, path, file_lines, position: None, draft: String::new(), search: None }
+    }
+
+    pub fn push(&mut self, command: &str) {
+        self.position = None;
+        self.search = None;
+
+        if command.trim().is_empty() || self.entries.last().map(|s| s.as_str()) == Some(command) {
+            return;
+        }
+
+        self.entries.push(command.to_string());
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+
+        let _ = self.append(command);
+    }
+
+    fn append(&mut self, command: &str) -> std::io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(())
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // rewriting on every command would be wasteful, rewriting never lets the file grow forever
+        if self.file_lines >= 2 * MAX_ENTRIES {
+            let mut content = self.entries.join("\n");
+            content.push('\n');
+            fs::write(path, content)?;
+            self.file_lines = self.entries.len();
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        writeln!(file, "{}", command)?;
+        self.file_lines += 1;
+
+        Ok(())
+    }
+
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            },
+            Some(0) => 0,
+            Some(position) => position - 1
+        };
+
+        self.position = Some(position);
+
+        self.entries.get(position).map(|s| s.as_str())
+    }
+
+    pub fn newer(&mut self) -> Option<&str> {
+        match self.position {
+            None => None,
+            Some(position) if position + 1 >= self.entries.len() => {
+                self.position = None;
+                Some(&self.draft)
+            },
+            Some(position) => {
+                self.position = Some(position + 1);
+                self.entries.get(position + 1).map(|s| s.as_str())
+            }
+        }
+    }
+
+    pub fn search(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    pub fn start_search(&mut self) {
+        self.position = None;
+        self.search = Some(Search { query: String::new(), found: None });
+    }
+
+    pub fn stop_search(&mut self) -> Option<String> {
+        let search = self.search.take()?;
+
+        search.found.and_then(|i| self.entries.get(i).cloned())
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        if let Some(search) = &mut self.search {
+            search.query.push(c);
+        }
+        self.find(self.entries.len());
+    }
+
+    pub fn search_pop(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.query.pop();
+        }
+        self.find(self.entries.len());
+    }
+
+    // Ctrl-R again looks for an older match
+    pub fn search_older(&mut self) {
+        let from = match self.search.as_ref().and_then(|s| s.found) {
+            Some(found) => found,
+            None => self.entries.len()
+        };
+        self.find(from);
+    }
+
+    pub fn matched(&self) -> Option<&str> {
+        self.search
+            .as_ref()
+            .and_then(|s| s.found)
+            .and_then(|i| self.entries.get(i))
+            .map(|s| s.as_str())
+    }
+
+    fn find(&mut self, before: usize) {
+        if let Some(search) = &mut self.search {
+            let found = self.entries[..before.min(self.entries.len())]
+                .iter()
+                .rposition(|entry| entry.contains(&search.query));
+
+            // keep the previous match when nothing older matches
+            if found.is_some() || before == self.entries.len() {
+                search.found = found;
+            }
+        }
+    }
+}
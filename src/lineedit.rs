@@ -0,0 +1,71 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::layout::Rect;
+use tui_input::{Input, InputRequest};
+
+// readline-ish bindings, tui_input's own crossterm backend is built against a newer crossterm
+pub fn request(key: KeyEvent) -> Option<InputRequest> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    let request = match key.code {
+        KeyCode::Char('a') if ctrl => InputRequest::GoToStart,
+        KeyCode::Char('e') if ctrl => InputRequest::GoToEnd,
+        KeyCode::Char('b') if ctrl => InputRequest::GoToPrevChar,
+        KeyCode::Char('f') if ctrl => InputRequest::GoToNextChar,
+        KeyCode::Char('w') if ctrl => InputRequest::DeletePrevWord,
+        KeyCode::Char('u') if ctrl => InputRequest::DeleteLine,
+        KeyCode::Char('k') if ctrl => InputRequest::DeleteTillEnd,
+        KeyCode::Char('b') if alt => InputRequest::GoToPrevWord,
+        KeyCode::Char('f') if alt => InputRequest::GoToNextWord,
+        KeyCode::Char('d') if alt => InputRequest::DeleteNextWord,
+        KeyCode::Char(_) if ctrl || alt => return None,
+        KeyCode::Char(c) => InputRequest::InsertChar(c),
+
+        KeyCode::Backspace if ctrl || alt => InputRequest::DeletePrevWord,
+        KeyCode::Backspace => InputRequest::DeletePrevChar,
+        KeyCode::Delete if ctrl || alt => InputRequest::DeleteNextWord,
+        KeyCode::Delete => InputRequest::DeleteNextChar,
+        KeyCode::Left if ctrl || alt => InputRequest::GoToPrevWord,
+        KeyCode::Left => InputRequest::GoToPrevChar,
+        KeyCode::Right if ctrl || alt => InputRequest::GoToNextWord,
+        KeyCode::Right => InputRequest::GoToNextChar,
+        KeyCode::Home => InputRequest::GoToStart,
+        KeyCode::End => InputRequest::GoToEnd,
+        _ => return None
+    };
+
+    Some(request)
+}
+
+// returns whether the value changed, the cursor alone moving does not count
+pub fn handle(input: &mut Input, key: KeyEvent) -> bool {
+    match request(key) {
+        Some(request) => input.handle(request).is_some_and(|changed| changed.value),
+        None => false
+    }
+}
+
+// pasted newlines would otherwise end up as raw control characters in a single-line field
+pub fn paste(input: &mut Input, text: &str) {
+    for c in text.chars() {
+        match c {
+            '\r' => {},
+            '\n' | '\t' => {
+                input.handle(InputRequest::InsertChar(' '));
+            },
+            c if c.is_control() => {},
+            c => {
+                input.handle(InputRequest::InsertChar(c));
+            }
+        }
+    }
+}
+
+// horizontal scroll for the paragraph and the terminal cursor position inside a bordered block
+pub fn layout(input: &Input, area: Rect) -> (u16, (u16, u16)) {
+    let width = area.width.saturating_sub(2) as usize;
+    let scroll = input.visual_scroll(width.saturating_sub(1));
+    let x = area.x + 1 + (input.visual_cursor().saturating_sub(scroll)) as u16;
+
+    (scroll as u16, (x, area.y + 1))
+}
@@ -1,5 +1,5 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEvent, KeyModifiers, KeyCode},
+    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -9,9 +9,24 @@ use tui::{
     Terminal,
 };
 
+pub mod address;
+pub mod clipboard;
+pub mod colors;
+pub mod completion;
+pub mod config;
 pub mod econtab;
+pub mod export;
+pub mod filter;
+pub mod find;
+pub mod history;
+pub mod lineedit;
+pub mod parser;
+pub mod profile;
+pub mod record;
+pub mod scrollback;
+pub mod sessionlog;
 pub mod state;
-pub mod stringarray;
+pub mod timeline;
 
 mod app;
 use app::*;
@@ -20,7 +35,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -33,7 +48,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -56,13 +72,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App, tick_rate: Dura
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if last_pressed_tick.elapsed() >= tick_rate {
+            match event::read()? {
+                Event::Key(key) if last_pressed_tick.elapsed() >= tick_rate => {
                     if app.on_key(key) {
                         return Ok(());
                     }
                     last_pressed_tick = Instant::now();
-                }
+                },
+                Event::Mouse(mouse) => app.on_mouse(mouse),
+                Event::Paste(text) => app.on_paste(text),
+                _ => {}
             }
         }
 
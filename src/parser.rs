@@ -0,0 +1,537 @@
+// turns raw econ output into typed events. servers print lines in one of these shapes:
+//   [5c4a1b2c][chat]: 0:-2:nameless tee: hello       teeworlds 0.6/0.7, hex timestamp
+//   [chat]: 0:-2:nameless tee: hello                 without timestamp
+//   2023-05-01 12:00:00 I chat: 0:-2:nameless tee: hello   ddnet
+//   Authentication successful. External console access granted.   econ itself
+
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub raw: String,
+    pub timestamp: Option<String>,
+    pub level: Option<Level>,
+    pub system: Option<String>,
+    pub text: String,
+    pub event: Event,
+    // `EconTab::received` of the command this line answers, a sent command belongs to itself
+    pub group: Option<u64>,
+    // when we got the line, `timestamp` is whatever the server printed
+    pub received_at: DateTime<Local>,
+    // unaffected by clock changes, relative times are measured from here
+    pub received_instant: Instant
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+    Debug,
+    Trace
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    pub id: u8,
+    pub name: String
+}
+
+// one row of `status` output, fields missing on older servers stay empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerInfo {
+    pub id: u8,
+    pub name: String,
+    pub clan: String,
+    pub address: String,
+    pub score: Option<i32>,
+    pub team: Option<i32>,
+    pub auth: Option<String>
+}
+
+// one row of `bans` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanEntry {
+    pub index: usize,
+    pub address: String,
+    // last address of a `ban_range`, None for a single address
+    pub end: Option<String>,
+    // None for permanent bans
+    pub minutes: Option<u32>,
+    pub reason: String
+}
+
+impl BanEntry {
+    // "1.2.3.0 - 1.2.3.255" for ranges
+    pub fn target(&self) -> String {
+        match &self.end {
+            Some(end) => format!("{} - {}", self.address, end),
+            None => self.address.clone()
+        }
+    }
+
+    // by address rather than index, the server renumbers its list as bans come and go
+    pub fn unban(&self) -> String {
+        match &self.end {
+            Some(end) => format!("unban_range {} {}", self.address, end),
+            None => format!("unban {}", self.address)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Chat { player: Player, text: String },
+    TeamChat { player: Player, team: i32, text: String },
+    // "*** ..." messages sent by the server itself
+    ServerChat { text: String },
+    Join { player: Player },
+    Leave { player: Player },
+    TeamChange { player: Player, team: i32 },
+    Kill { killer: Player, victim: Player, weapon: i32 },
+    MapChange { map: String },
+    VoteStarted { caller: String, description: String, reason: String },
+    VotePassed,
+    VoteFailed,
+    RconAuth { id: u8, level: String },
+    EconAuth { success: bool },
+    Status(PlayerInfo),
+    Ban(BanEntry),
+    BanCount(usize),
+    // a command we sent, echoed into the scrollback
+    Sent,
+    Log
+}
+
+impl Event {
+    // what a command can print back, chat and game events happen whether or not one was sent
+    pub fn can_reply(&self) -> bool {
+        matches!(self, Self::Status(_) | Self::Ban(_) | Self::BanCount(_) | Self::Log)
+    }
+
+    // names the event in colour rules and json records
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Chat { .. } => "chat",
+            Self::TeamChat { .. } => "teamchat",
+            Self::ServerChat { .. } => "server_chat",
+            Self::Join { .. } => "join",
+            Self::Leave { .. } => "leave",
+            Self::TeamChange { .. } => "team",
+            Self::Kill { .. } => "kill",
+            Self::MapChange { .. } => "map",
+            Self::VoteStarted { .. } | Self::VotePassed | Self::VoteFailed => "vote",
+            Self::RconAuth { .. } | Self::EconAuth { .. } => "auth",
+            Self::Status(_) => "status",
+            Self::Ban(_) | Self::BanCount(_) => "ban",
+            Self::Sent => "sent",
+            Self::Log => "log"
+        }
+    }
+
+    pub fn player(&self) -> Option<&Player> {
+        match self {
+            Self::Chat { player, .. } | Self::TeamChat { player, .. } | Self::Join { player } |
+            Self::Leave { player } | Self::TeamChange { player, .. } => Some(player),
+            Self::Kill { killer, .. } => Some(killer),
+            _ => None
+        }
+    }
+}
+
+impl Line {
+    pub fn sent(command: &str) -> Self {
+        let now = Local::now();
+        let timestamp = now.format("%H:%M:%S").to_string();
+
+        Line {
+            raw: format!("[{}] > {}", timestamp, command),
+            timestamp: Some(timestamp),
+            level: None,
+            system: None,
+            text: command.to_string(),
+            event: Event::Sent,
+            group: None,
+            received_at: now,
+            received_instant: Instant::now()
+        }
+    }
+}
+
+pub fn parse(raw: String) -> Line {
+    let (timestamp, level, system, text) = split_header(&raw);
+    let text = text.to_string();
+    let event = match system {
+        Some(system) => parse_event(system, &text),
+        None => parse_econ(&text)
+    };
+
+    Line {
+        timestamp: timestamp.map(|s| s.to_string()),
+        level,
+        system: system.map(|s| s.to_string()),
+        text,
+        event,
+        raw,
+        group: None,
+        received_at: Local::now(),
+        received_instant: Instant::now()
+    }
+}
+
+fn split_header(raw: &str) -> (Option<&str>, Option<Level>, Option<&str>, &str) {
+    if let Some(rest) = raw.strip_prefix('[') {
+        let (first, rest) = match rest.split_once(']') {
+            Some(split) => split,
+            None => return (None, None, None, raw)
+        };
+
+        // [timestamp][system]: text
+        if let Some(rest) = rest.strip_prefix('[') {
+            if let Some((system, text)) = rest.split_once("]: ") {
+                return (Some(first), None, Some(system), text);
+            }
+        }
+
+        // [system]: text
+        if let Some(text) = rest.strip_prefix(": ") {
+            return (None, None, Some(first), text);
+        }
+
+        return (None, None, None, raw);
+    }
+
+    // YYYY-MM-DD HH:MM:SS L system: text
+    let bytes = raw.as_bytes();
+    if bytes.len() > 22 && bytes[4] == b'-' && bytes[7] == b'-' && bytes[10] == b' ' && bytes[13] == b':' && bytes[19] == b' ' && bytes[21] == b' ' {
+        let level = match bytes[20] {
+            b'E' => Some(Level::Error),
+            b'W' => Some(Level::Warning),
+            b'I' => Some(Level::Info),
+            b'D' => Some(Level::Debug),
+            b'T' => Some(Level::Trace),
+            _ => None
+        };
+
+        if let (Some(level), Some((system, text))) = (level, raw[22..].split_once(": ")) {
+            return (Some(&raw[..19]), Some(level), Some(system), text);
+        }
+    }
+
+    (None, None, None, raw)
+}
+
+fn parse_econ(text: &str) -> Event {
+    if text.starts_with("Authentication successful") {
+        Event::EconAuth { success: true }
+    }
+    else if text.starts_with("Wrong password") {
+        Event::EconAuth { success: false }
+    }
+    else {
+        Event::Log
+    }
+}
+
+fn parse_event(system: &str, text: &str) -> Event {
+    // 0.6 prints "[Server]", everything else lowercase
+    let event = match system.to_ascii_lowercase().as_str() {
+        "chat" | "teamchat" => parse_chat(system, text),
+        "game" => parse_game(text),
+        "server" => parse_server(text),
+        "datafile" => parse_map(text),
+        "net_ban" => parse_ban(text),
+        _ => None
+    };
+
+    event.unwrap_or(Event::Log)
+}
+
+// "0:-2:name: text" where -2 is the whole server, anything else a team
+fn parse_chat(system: &str, text: &str) -> Option<Event> {
+    if let Some(text) = text.strip_prefix("*** ") {
+        let event = if text.contains(" called vote ") {
+            parse_vote(text)?
+        }
+        else if text.starts_with("Vote passed") {
+            Event::VotePassed
+        }
+        else if text.starts_with("Vote failed") {
+            Event::VoteFailed
+        }
+        else {
+            Event::ServerChat { text: text.to_string() }
+        };
+
+        return Some(event);
+    }
+
+    let mut parts = text.splitn(3, ':');
+    let id = parts.next()?.parse::<i32>().ok()?;
+    let team = parts.next()?.parse::<i32>().ok()?;
+    let (name, text) = parts.next()?.split_once(": ")?;
+
+    if id < 0 {
+        return Some(Event::ServerChat { text: text.to_string() });
+    }
+
+    let player = Player { id: id as u8, name: name.to_string() };
+
+    if system == "teamchat" || team >= 0 {
+        Some(Event::TeamChat { player, team, text: text.to_string() })
+    }
+    else {
+        Some(Event::Chat { player, text: text.to_string() })
+    }
+}
+
+// 'name' called vote to change server option 'description' (reason)
+fn parse_vote(text: &str) -> Option<Event> {
+    let (caller, rest) = text.strip_prefix('\'')?.split_once("' called vote ")?;
+    let (_, rest) = rest.split_once('\'')?;
+    let (description, rest) = rest.split_once('\'')?;
+    let reason = rest.trim().trim_start_matches('(').trim_end_matches(')');
+
+    Some(Event::VoteStarted {
+        caller: caller.to_string(),
+        description: description.to_string(),
+        reason: reason.to_string()
+    })
+}
+
+fn parse_game(text: &str) -> Option<Event> {
+    let (kind, rest) = text.split_once(' ')?;
+
+    match kind {
+        "kill" => Some(Event::Kill {
+            killer: quoted_player(rest, "killer='")?,
+            victim: quoted_player(rest, "victim='")?,
+            weapon: value(rest, "weapon=")?.parse().ok()?
+        }),
+        "leave" => Some(Event::Leave { player: quoted_player(rest, "player='")? }),
+        "team_join" => Some(Event::TeamChange {
+            player: quoted_player(rest, "player='")?,
+            team: value(rest, "team=")?.parse().ok()?
+        }),
+        "join" => Some(Event::Join { player: quoted_player(rest, "player='")? }),
+        _ => None
+    }
+}
+
+fn parse_server(text: &str) -> Option<Event> {
+    if text.starts_with("id=") {
+        return parse_status(text).map(Event::Status);
+    }
+
+    // the name is only printed by the team_join that follows
+    if let Some(rest) = text.strip_prefix("player has entered the game. ClientID=") {
+        let id = rest.split(' ').next()?.parse().ok()?;
+        return Some(Event::Join { player: Player { id, name: String::new() } });
+    }
+
+    // ClientID=0 authed (admin) / ClientID=0 authed with key=default_admin (admin)
+    if let Some(rest) = text.strip_prefix("ClientID=") {
+        let (id, rest) = rest.split_once(' ')?;
+        if rest.starts_with("authed") {
+            let level = rest.rsplit_once('(')?.1.trim_end_matches(')');
+            return Some(Event::RconAuth { id: id.parse().ok()?, level: level.to_string() });
+        }
+        return None;
+    }
+
+    parse_map(text)
+}
+
+// id=0 addr=1.2.3.4:8303 name='foo' score=0 (Admin)
+// id=0 addr=<{1.2.3.4:8303}> name='foo' client=16050 secure=yes flags=0 key=default_admin (Admin)
+fn parse_status(text: &str) -> Option<PlayerInfo> {
+    let name_start = text.find("name='")? + "name='".len();
+    let name_len = text[name_start..].find("' ").or_else(|| text[name_start..].rfind('\''))?;
+    // everything after the name could be confused with a name like "x score=1"
+    let rest = &text[name_start + name_len..];
+
+    let auth = match rest.rsplit_once('(') {
+        Some((_, level)) if level.ends_with(')') => Some(level.trim_end_matches(')').to_string()),
+        _ => None
+    };
+
+    Some(PlayerInfo {
+        id: value(text, "id=")?.parse().ok()?,
+        name: text[name_start..name_start + name_len].to_string(),
+        clan: quoted(rest, "clan='").unwrap_or_default().to_string(),
+        address: value(text, "addr=").unwrap_or_default().trim_start_matches("<{").trim_end_matches("}>").to_string(),
+        score: value(rest, "score=").and_then(|s| s.parse().ok()),
+        team: value(rest, "team=").and_then(|s| s.parse().ok()),
+        auth
+    })
+}
+
+fn quoted<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let rest = &text[text.find(key)? + key.len()..];
+
+    Some(&rest[..rest.find('\'')?])
+}
+
+// #0 1.2.3.4 banned for 5 minutes (Banned by vote)
+// #1 1.2.3.0-1.2.3.255 banned for life (no reason)
+// 1 ban, 2 bans, or 2 ban(s) on older servers
+fn parse_ban(text: &str) -> Option<Event> {
+    if let Some(count) = [" ban(s)", " bans", " ban"].iter().find_map(|suffix| text.strip_suffix(suffix)) {
+        return Some(Event::BanCount(count.parse().ok()?));
+    }
+
+    let (index, rest) = text.strip_prefix('#')?.split_once(' ')?;
+    // "'1.2.3.4'" or "'1.2.3.0' - '1.2.3.255'" for ranges
+    let (address, rest) = rest.split_once(" banned for ")?;
+    let (address, end) = match address.split_once(" - ") {
+        Some((start, end)) => (start, Some(end.trim_matches('\'').to_string())),
+        None => (address, None)
+    };
+    let (duration, reason) = rest.split_once(" (")?;
+
+    let minutes = match duration {
+        "life" => None,
+        duration => Some(duration.split(' ').next()?.parse().ok()?)
+    };
+
+    Some(Event::Ban(BanEntry {
+        index: index.parse().ok()?,
+        address: address.trim_matches('\'').to_string(),
+        end,
+        minutes,
+        reason: reason.strip_suffix(')').unwrap_or(reason).to_string()
+    }))
+}
+
+// "loading done. datafile='maps/dm1.map'" or "maps/dm1.map crc is ..."
+fn parse_map(text: &str) -> Option<Event> {
+    let start = text.find("maps/")? + "maps/".len();
+    let end = start + text[start..].find(".map")?;
+
+    if !(text.contains("loading done") || text.contains(" crc is ") || text.contains(" sha256 is ")) {
+        return None;
+    }
+
+    Some(Event::MapChange { map: text[start..end].to_string() })
+}
+
+// player='12:name with spaces'
+fn quoted_player(text: &str, key: &str) -> Option<Player> {
+    let rest = &text[text.find(key)? + key.len()..];
+    let (id, rest) = rest.split_once(':')?;
+    let name = &rest[..rest.find("' ").or_else(|| rest.rfind('\''))?];
+
+    Some(Player { id: id.parse().ok()?, name: name.to_string() })
+}
+
+fn value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let rest = &text[text.find(key)? + key.len()..];
+
+    rest.split(' ').next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(raw: &str) -> Event {
+        parse(raw.to_string()).event
+    }
+
+    fn player(id: u8, name: &str) -> Player {
+        Player { id, name: name.to_string() }
+    }
+
+    #[test]
+    fn status() {
+        let cases = [
+            (
+                "[server]: id=0 addr=1.2.3.4:8303 name='nameless tee' score=0",
+                PlayerInfo { id: 0, name: "nameless tee".into(), address: "1.2.3.4:8303".into(), score: Some(0), ..Default::default() }
+            ),
+            (
+                "[5c4a1b2c][server]: id=3 addr=1.2.3.4:51234 name='foo' clan='bar' score=12 team=1",
+                PlayerInfo { id: 3, name: "foo".into(), clan: "bar".into(), address: "1.2.3.4:51234".into(), score: Some(12), team: Some(1), auth: None }
+            ),
+            (
+                "2023-05-01 12:00:00 I server: id=1 addr=<{1.2.3.4:8303}> name='foo' client=16050 secure=yes flags=0 key=default_admin (Admin)",
+                PlayerInfo { id: 1, name: "foo".into(), address: "1.2.3.4:8303".into(), auth: Some("Admin".into()), ..Default::default() }
+            ),
+            // a name that looks like the fields after it
+            (
+                "[server]: id=2 addr=[::1]:8303 name='x score=1' score=5",
+                PlayerInfo { id: 2, name: "x score=1".into(), address: "[::1]:8303".into(), score: Some(5), ..Default::default() }
+            )
+        ];
+
+        for (raw, info) in cases {
+            assert_eq!(event(raw), Event::Status(info), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn bans() {
+        let ban = |index, address: &str, end: Option<&str>, minutes, reason: &str| Event::Ban(BanEntry {
+            index,
+            address: address.to_string(),
+            end: end.map(|e| e.to_string()),
+            minutes,
+            reason: reason.to_string()
+        });
+        let cases = [
+            ("[net_ban]: #0 '1.2.3.4' banned for 5 minutes (Banned by vote)", ban(0, "1.2.3.4", None, Some(5), "Banned by vote")),
+            ("[net_ban]: #1 '1.2.3.0' - '1.2.3.255' banned for life (No reason given)", ban(1, "1.2.3.0", Some("1.2.3.255"), None, "No reason given")),
+            ("[net_ban]: #2 '10.0.0.1' - '10.0.0.9' banned for 30 minutes (flood (again))", ban(2, "10.0.0.1", Some("10.0.0.9"), Some(30), "flood (again)")),
+            ("2023-05-01 12:00:00 I net_ban: #0 '1.2.3.4' banned for 1 minute (spam)", ban(0, "1.2.3.4", None, Some(1), "spam")),
+            ("[net_ban]: 1 ban", Event::BanCount(1)),
+            ("[net_ban]: 3 bans", Event::BanCount(3)),
+            ("[net_ban]: 0 bans", Event::BanCount(0)),
+            ("[net_ban]: 2 ban(s)", Event::BanCount(2)),
+            ("[net_ban]: banned for 5 minutes", Event::Log)
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(event(raw), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn chat() {
+        let cases = [
+            ("[chat]: 0:-2:nameless tee: hello", Event::Chat { player: player(0, "nameless tee"), text: "hello".into() }),
+            ("[5c4a1b2c][chat]: 4:-2:foo: a: b", Event::Chat { player: player(4, "foo"), text: "a: b".into() }),
+            ("[teamchat]: 1:0:foo: gg", Event::TeamChat { player: player(1, "foo"), team: 0, text: "gg".into() }),
+            ("2023-05-01 12:00:00 I chat: 2:1:bar: go", Event::TeamChat { player: player(2, "bar"), team: 1, text: "go".into() }),
+            ("[chat]: -1:-2:: welcome", Event::ServerChat { text: "welcome".into() }),
+            ("[chat]: *** 'foo' entered and joined the game", Event::ServerChat { text: "'foo' entered and joined the game".into() }),
+            (
+                "[chat]: *** 'foo' called vote to change server option 'restart' (no reason)",
+                Event::VoteStarted { caller: "foo".into(), description: "restart".into(), reason: "no reason".into() }
+            ),
+            ("[chat]: *** Vote passed", Event::VotePassed),
+            ("[chat]: *** Vote failed", Event::VoteFailed),
+            ("[chat]: not a chat line", Event::Log)
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(event(raw), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn map() {
+        let map = |name: &str| Event::MapChange { map: name.to_string() };
+        let cases = [
+            ("[datafile]: loading done. datafile='maps/dm1.map'", map("dm1")),
+            ("2023-05-01 12:00:00 I server: maps/ctf5.map crc is 1234abcd", map("ctf5")),
+            ("[server]: maps/dm1.map sha256 is 0123456789abcdef", map("dm1")),
+            ("[datafile]: loading maps/dm1.map", Event::Log)
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(event(raw), expected, "{}", raw);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fs::{self, OpenOptions}, io::{self, Write}, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::config_dir, sessionlog::LogFormat};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub address: String,
+    #[serde(default)]
+    pub password: String,
+    // connections sharing a group are listed, folded and acted on together
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub group: String,
+    // session logging is opt-in per connection
+    #[serde(default, skip_serializing_if = "LogFormat::is_off")]
+    pub log: LogFormat
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(default, rename = "profile")]
+    pub profiles: Vec<Profile>
+}
+
+impl Profiles {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("profiles.toml"))
+    }
+
+    // a missing file is an empty list, anything else that can not be read is an error
+    // so the caller never saves over profiles it did not load
+    pub fn load() -> io::Result<Self> {
+        let content = match Self::path().map(fs::read_to_string) {
+            Some(Ok(content)) => content,
+            Some(Err(error)) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            _ => return Ok(Self::default())
+        };
+
+        toml::from_str(&content).map_err(|e| {
+            let line = e.span().map_or(1, |span| content[..span.start].matches('\n').count() + 1);
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, e.message()))
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        // the file holds econ passwords, keep it to the owner
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // mode only applies to a new file, tighten one saved by an older version too
+            if path.exists() {
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+
+        options.open(path)?.write_all(content.as_bytes())
+    }
+
+    // profiles are keyed by name, saving an existing one overwrites it
+    pub fn insert(&mut self, profile: Profile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile)
+        }
+    }
+}
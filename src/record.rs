@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+use crate::parser::{Event, Line};
+
+// a line as json in session logs and exports
+#[derive(Serialize)]
+pub struct Record<'a> {
+    time: String,
+    // only session logs name the connection, an export is about one already
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server: Option<&'a str>,
+    kind: &'static str,
+    sent: bool,
+    line: &'a str
+}
+
+impl<'a> Record<'a> {
+    pub fn new(line: &'a Line, server: Option<&'a str>) -> Self {
+        Self {
+            time: line.received_at.to_rfc3339(),
+            server,
+            kind: line.event.kind(),
+            sent: line.event == Event::Sent,
+            line: &line.raw
+        }
+    }
+}
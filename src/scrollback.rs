@@ -0,0 +1,55 @@
+use std::{collections::{vec_deque, VecDeque}, ops::Range};
+
+// keeps at most `capacity` lines, dropping the oldest ones first
+#[derive(Debug, Clone)]
+pub struct Scrollback<T> {
+    lines: VecDeque<T>,
+    capacity: usize
+}
+
+impl<T> Scrollback<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self { lines: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    // returns the line that had to make room
+    pub fn push(&mut self, line: T) -> Option<T> {
+        let mut dropped = None;
+
+        while self.lines.len() >= self.capacity {
+            dropped = self.lines.pop_front();
+        }
+
+        self.lines.push_back(line);
+
+        dropped
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.lines.get(index)
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.lines.iter()
+    }
+
+    pub fn range(&self, range: Range<usize>) -> vec_deque::Iter<'_, T> {
+        self.lines.range(range)
+    }
+}
+
+impl<T> Extend<T> for Scrollback<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for line in iter {
+            self.push(line);
+        }
+    }
+}
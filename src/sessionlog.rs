@@ -0,0 +1,124 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, Write}, path::{Path, PathBuf}};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::{data_dir, file_name}, parser::{Event, Line}, record::Record};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Off,
+    Text,
+    Json
+}
+
+impl LogFormat {
+    pub fn is_off(&self) -> bool {
+        *self == Self::Off
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Text,
+            Self::Text => Self::Json,
+            Self::Json => Self::Off
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Text => "text",
+            Self::Json => "json"
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "jsonl",
+            _ => "log"
+        }
+    }
+}
+
+// appends a tab's console to logs/<name>/<date>.log, rolling over to <date>.1.log, <date>.2.log
+// and so on once a file reaches `max_bytes`
+pub struct SessionLog {
+    pub format: LogFormat,
+    name: String,
+    dir: PathBuf,
+    max_bytes: u64,
+    file: Option<OpenLog>
+}
+
+struct OpenLog {
+    date: NaiveDate,
+    file: File,
+    size: u64
+}
+
+impl SessionLog {
+    pub fn new(name: &str, format: LogFormat, max_bytes: u64) -> Option<Self> {
+        if format.is_off() {
+            return None;
+        }
+
+        Some(Self {
+            format,
+            name: name.to_string(),
+            dir: data_dir()?.join("logs").join(file_name(name)),
+            max_bytes,
+            file: None
+        })
+    }
+
+    pub fn write(&mut self, line: &Line) -> io::Result<()> {
+        let entry = match self.format {
+            LogFormat::Json => serde_json::to_string(&Record::new(line, Some(&self.name)))?,
+            // sent commands already carry their time
+            _ if line.event == Event::Sent => line.raw.clone(),
+            _ => format!("[{}] {}", line.received_at.format("%H:%M:%S"), line.raw)
+        };
+
+        let log = self.open(line.received_at.date_naive())?;
+
+        writeln!(log.file, "{}", entry)?;
+        log.size += entry.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn open(&mut self, date: NaiveDate) -> io::Result<&mut OpenLog> {
+        let current = self.file.as_ref().is_some_and(|log| log.date == date && log.size < self.max_bytes);
+
+        if !current {
+            fs::create_dir_all(&self.dir)?;
+
+            let path = self.dir.join(format!("{}.{}", date, self.format.extension()));
+            let size = fs::metadata(&path).map_or(0, |m| m.len());
+
+            if size >= self.max_bytes {
+                self.rotate(date, &path)?;
+            }
+
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let size = file.metadata()?.len();
+
+            self.file = Some(OpenLog { date, file, size });
+        }
+
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    // the full file moves to the first free numbered name, today's log always has the plain name
+    fn rotate(&self, date: NaiveDate, path: &Path) -> io::Result<()> {
+        let rotated = (1..)
+            .map(|n| self.dir.join(format!("{}.{}.{}", date, n, self.format.extension())))
+            .find(|p| !p.exists())
+            .unwrap();
+
+        fs::rename(path, rotated)
+    }
+}
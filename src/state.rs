@@ -1,36 +1,503 @@
-use crate::stringarray::StringArray;
+use std::{collections::BTreeSet, io, net::SocketAddr};
 
-#[derive(Debug, Clone, Copy)]
+use tui_input::Input;
+
+use chrono::Local;
+
+use crate::{address::MAX_RESOLVED, config::file_name, export::{ExportFormat, ExportScope}, parser::BanEntry};
+
+#[derive(Debug, Clone)]
 pub enum Screen {
     Welcome,
     Main(Main),
     AddConnection(AddConnection),
-    Exit
+    Bans(Bans),
+    // the screen Esc goes back to
+    Exit(Main)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Main {
     pub active: MainElements,
-    pub connection: u8
+    pub connection: u8,
+    // selected row of the player list
+    pub player: usize,
+    pub menu: Option<PlayerMenu>,
+    // connections picked with Space for a broadcast
+    pub marked: BTreeSet<u8>,
+    pub broadcast: Option<Broadcast>,
+    // set while the Connections cursor is on a group header instead of `connection`
+    pub group: Option<String>,
+    pub collapsed: BTreeSet<String>,
+    // typing into the console filter bar
+    pub filtering: bool,
+    // typing a `/` search
+    pub finding: bool,
+    pub export: Option<Export>,
+    // the focused pane shows the merged "All" timeline instead of `connection`
+    pub timeline: bool,
+    // console panes side by side, the focused one always shows `connection`
+    pub panes: Vec<Pane>,
+    pub pane: usize,
+    pub split: SplitDirection
 }
 
 impl Main {
     pub fn new() -> Self {
-        Self { active: MainElements::Add, connection: 0 }
+        Self {
+            active: MainElements::Add,
+            connection: 0,
+            player: 0,
+            menu: None,
+            marked: BTreeSet::new(),
+            broadcast: None,
+            group: None,
+            collapsed: BTreeSet::new(),
+            filtering: false,
+            finding: false,
+            export: None,
+            timeline: false,
+            panes: vec![Pane { connection: 0, timeline: false, weight: 1 }],
+            pane: 0,
+            split: SplitDirection::Horizontal
+        }
+    }
+
+    pub fn pane_connection(&self, pane: usize) -> u8 {
+        if pane == self.pane {
+            self.connection
+        }
+        else {
+            self.panes[pane].connection
+        }
+    }
+
+    pub fn pane_timeline(&self, pane: usize) -> bool {
+        if pane == self.pane {
+            self.timeline
+        }
+        else {
+            self.panes[pane].timeline
+        }
+    }
+
+    pub fn focus_pane(&mut self, pane: usize) {
+        self.panes[self.pane].connection = self.connection;
+        self.panes[self.pane].timeline = self.timeline;
+        self.pane = pane;
+        self.connection = self.panes[pane].connection;
+        self.timeline = self.panes[pane].timeline;
+        self.group = None;
+        self.player = 0;
+    }
+
+    // the new pane goes next to the focused one and takes the focus
+    pub fn split_pane(&mut self, connection: u8) {
+        self.panes[self.pane].connection = self.connection;
+        self.panes[self.pane].timeline = self.timeline;
+        self.panes.insert(self.pane + 1, Pane { connection, timeline: false, weight: 1 });
+        self.focus_pane(self.pane + 1);
+    }
+
+    pub fn close_pane(&mut self) {
+        if self.panes.len() > 1 {
+            self.panes.remove(self.pane);
+            self.pane = self.pane.min(self.panes.len() - 1);
+            self.connection = self.panes[self.pane].connection;
+            self.timeline = self.panes[self.pane].timeline;
+            self.group = None;
+            self.player = 0;
+        }
     }
+
+    pub fn resize_pane(&mut self, grow: bool) {
+        let weight = &mut self.panes[self.pane].weight;
+
+        *weight = if grow { (*weight + 1).min(MAX_PANE_WEIGHT) } else { weight.saturating_sub(1).max(1) };
+    }
+}
+
+const MAX_PANE_WEIGHT: u16 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pane {
+    pub connection: u8,
+    pub timeline: bool,
+    // share of the console area relative to the other panes
+    pub weight: u16
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    // panes side by side
+    Horizontal,
+    // panes stacked
+    Vertical
 }
 
-#[derive(Debug, Clone, Copy)]
+impl SplitDirection {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Horizontal => Self::Vertical,
+            Self::Vertical => Self::Horizontal
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub scope: ExportScope,
+    pub format: ExportFormat,
+    pub path: Input,
+    // lines in `scope` when it was picked
+    pub count: usize,
+    // the path already exists and the next Enter replaces it
+    pub overwrite: bool,
+    // where the file went or why it could not be written
+    pub result: Option<Result<String, String>>
+}
+
+impl Export {
+    pub fn new(name: &str, scope: ExportScope, count: usize) -> Self {
+        let path = format!("{}-{}.txt", file_name(name), Local::now().format("%Y%m%d-%H%M%S"));
+
+        Self { scope, format: ExportFormat::Text, path: Input::new(path), count, overwrite: false, result: None }
+    }
+
+    // keeps the extension in step with the format unless the user picked their own
+    pub fn set_format(&mut self, format: ExportFormat) {
+        let path = self.path.value();
+
+        if let Some(stem) = path.strip_suffix(&format!(".{}", self.format.extension())) {
+            self.path = Input::new(format!("{}.{}", stem, format.extension()));
+            self.overwrite = false;
+        }
+        self.format = format;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Broadcast {
+    pub command: Input,
+    // empty until sent, then one entry per marked connection
+    pub results: Vec<BroadcastResult>
+}
+
+#[derive(Debug, Clone)]
+pub struct BroadcastResult {
+    pub connection: u8,
+    pub error: Option<String>,
+    // `EconTab::received` at the time of sending, everything after it is the reply
+    pub since: u64
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerMenu {
+    pub id: u8,
+    pub name: String,
+    pub action: PlayerAction,
+    pub step: PlayerMenuStep,
+    pub duration: Input,
+    pub reason: Input
+}
+
+impl PlayerMenu {
+    pub fn new(id: u8, name: String) -> Self {
+        Self {
+            id,
+            name,
+            action: PlayerAction::Kick,
+            step: PlayerMenuStep::Choose,
+            duration: Input::default(),
+            reason: Input::default()
+        }
+    }
+
+    // the prompts each action goes through before it is sent
+    pub fn next_step(&self) -> Option<PlayerMenuStep> {
+        let next = match (self.step, self.action) {
+            (PlayerMenuStep::Choose, PlayerAction::Ban | PlayerAction::Mute) => PlayerMenuStep::Duration,
+            (PlayerMenuStep::Choose, PlayerAction::Kick | PlayerAction::VoteKick) => PlayerMenuStep::Reason,
+            (PlayerMenuStep::Duration, PlayerAction::Ban) => PlayerMenuStep::Reason,
+            (PlayerMenuStep::Choose | PlayerMenuStep::Duration | PlayerMenuStep::Reason, action) if action.destructive() => PlayerMenuStep::Confirm,
+            _ => return None
+        };
+
+        Some(next)
+    }
+
+    pub fn command(&self) -> String {
+        self.action.command(self.id, self.duration.value().trim(), self.reason.value().trim())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerMenuStep {
+    Choose,
+    Duration,
+    Reason,
+    Confirm
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PlayerAction {
+    Kick,
+    Ban,
+    Mute,
+    Spectate,
+    VoteKick
+}
+
+impl PlayerAction {
+    pub const ALL: [Self; 5] = [Self::Kick, Self::Ban, Self::Mute, Self::Spectate, Self::VoteKick];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Kick => Self::Ban,
+            Self::Ban => Self::Mute,
+            Self::Mute => Self::Spectate,
+            Self::Spectate => Self::VoteKick,
+            Self::VoteKick => Self::Kick
+        }
+    }
+
+    pub fn prev(&self) -> Self {
+        match self {
+            Self::Kick => Self::VoteKick,
+            Self::Ban => Self::Kick,
+            Self::Mute => Self::Ban,
+            Self::Spectate => Self::Mute,
+            Self::VoteKick => Self::Spectate
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Kick => "Kick",
+            Self::Ban => "Ban",
+            Self::Mute => "Mute",
+            Self::Spectate => "Move to spectators",
+            Self::VoteKick => "Vote kick"
+        }
+    }
+
+    pub fn destructive(&self) -> bool {
+        matches!(self, Self::Kick | Self::Ban | Self::VoteKick)
+    }
+
+    // ban takes minutes, ddnet's muteid takes seconds
+    pub fn command(&self, id: u8, duration: &str, reason: &str) -> String {
+        let duration: String = duration.chars().filter(char::is_ascii_digit).collect();
+        let duration = duration.as_str();
+        let reason = argument(reason);
+        let command = match self {
+            Self::Kick => format!("kick {} {}", id, reason),
+            Self::Ban => format!("ban {} {} {}", id, if duration.is_empty() { "5" } else { duration }, reason),
+            Self::Mute => format!("muteid {} {}", id, if duration.is_empty() { "60" } else { duration }),
+            Self::Spectate => format!("set_team {} -1", id),
+            Self::VoteKick => format!("force_vote kick {} {}", id, reason)
+        };
+
+        command.trim_end().to_string()
+    }
+}
+
+// user text going into a console command. `;` would start a command of its own, a quote would
+// end the argument early and a newline ends the whole line
+fn argument(text: &str) -> String {
+    text
+        .chars()
+        .filter(|c| !matches!(c, ';' | '"') && !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[derive(Debug, Clone)]
+pub struct Bans {
+    // where Esc goes back to, also names the connection whose bans are shown
+    pub main: Main,
+    pub selected: usize,
+    pub form: Option<BanForm>,
+    // the ban waiting for confirmation before `unban`
+    pub removing: Option<BanEntry>
+}
+
+impl Bans {
+    pub fn new(main: Main) -> Self {
+        Self { main, selected: 0, form: None, removing: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BanFormKind {
+    Add,
+    // minutes the ban has left, the form asks how many to add on top
+    Extend(u32)
+}
+
+#[derive(Debug, Clone)]
+pub struct BanForm {
+    pub kind: BanFormKind,
+    pub active: BanFormElements,
+    pub fields: [Input; 3]
+}
+
+impl BanForm {
+    pub fn add() -> Self {
+        Self { kind: BanFormKind::Add, active: BanFormElements::Address, fields: Default::default() }
+    }
+
+    pub fn extend(ban: &BanEntry, remaining: u32) -> Self {
+        Self {
+            kind: BanFormKind::Extend(remaining),
+            active: BanFormElements::Minutes,
+            fields: [Input::new(ban.target()), Input::default(), Input::new(ban.reason.clone())]
+        }
+    }
+
+    pub fn value(&self, element: BanFormElements) -> &str {
+        self.fields[element as usize].value().trim()
+    }
+
+    // banning an address again replaces its ban, so extending is a ban with the summed duration
+    pub fn command(&self) -> Option<String> {
+        let address = argument(self.value(BanFormElements::Address));
+        let minutes = match (self.kind, self.value(BanFormElements::Minutes).parse::<u32>()) {
+            (BanFormKind::Add, Ok(minutes)) => minutes,
+            (BanFormKind::Add, Err(_)) if self.value(BanFormElements::Minutes).is_empty() => 5,
+            (BanFormKind::Extend(remaining), Ok(minutes)) if minutes > 0 => remaining.saturating_add(minutes),
+            _ => return None
+        };
+
+        if address.is_empty() {
+            return None;
+        }
+
+        let reason = argument(self.value(BanFormElements::Reason));
+        let command = match address.split_once('-') {
+            Some((start, end)) => format!("ban_range {} {} {} {}", start.trim(), end.trim(), minutes, reason),
+            None => format!("ban {} {} {}", address, minutes, reason)
+        };
+
+        Some(command.trim_end().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BanFormElements {
+    Address,
+    Minutes,
+    Reason
+}
+
+impl BanFormElements {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Address => Self::Minutes,
+            Self::Minutes => Self::Reason,
+            Self::Reason => Self::Address
+        }
+    }
+
+    pub fn prev(&self) -> Self {
+        match self {
+            Self::Address => Self::Reason,
+            Self::Minutes => Self::Address,
+            Self::Reason => Self::Minutes
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct AddConnection {
+    // where the dialog goes back to once it is left
+    pub main: Main,
     pub active: AddConnectionElements,
-    pub fields: [StringArray<64>; 3]
+    pub fields: [Input; 4],
+    pub error: Option<ConnectError>,
+    pub pending: bool,
+    pub resolved: [Option<SocketAddr>; MAX_RESOLVED],
+    pub choice: usize
 }
 
 impl AddConnection {
-    pub fn new() -> Self {
+    pub fn new(main: Main) -> Self {
         Self {
+            main,
             active: AddConnectionElements::Name,
-            fields: [StringArray::new(); 3]
+            fields: Default::default(),
+            error: None,
+            pending: false,
+            resolved: [None; MAX_RESOLVED],
+            choice: 0
+        }
+    }
+
+    // edits invalidate the last error and, for the address, whatever it resolved to
+    pub fn field_changed(&mut self) {
+        self.error = None;
+        if self.active == AddConnectionElements::Address {
+            self.set_resolved(&[]);
+        }
+    }
+
+    pub fn resolved_count(&self) -> usize {
+        self.resolved.iter().take_while(|a| a.is_some()).count()
+    }
+
+    pub fn chosen(&self) -> Option<SocketAddr> {
+        self.resolved.get(self.choice).copied().flatten()
+    }
+
+    pub fn set_resolved(&mut self, addresses: &[SocketAddr]) {
+        self.resolved = [None; MAX_RESOLVED];
+        for (slot, address) in self.resolved.iter_mut().zip(addresses) {
+            *slot = Some(*address);
+        }
+        self.choice = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectError {
+    InvalidAddress,
+    Unresolved,
+    Refused,
+    Timeout,
+    Closed,
+    WrongPassword,
+    Other(io::ErrorKind)
+}
+
+impl ConnectError {
+    pub fn field(&self) -> AddConnectionElements {
+        match self {
+            Self::WrongPassword => AddConnectionElements::Password,
+            _ => AddConnectionElements::Address
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::InvalidAddress => "invalid address".to_string(),
+            Self::Unresolved => "could not resolve host".to_string(),
+            Self::Refused => "connection refused".to_string(),
+            Self::Timeout => "timed out".to_string(),
+            Self::Closed => "connection closed".to_string(),
+            Self::WrongPassword => "wrong password".to_string(),
+            Self::Other(kind) => kind.to_string()
+        }
+    }
+}
+
+impl From<io::Error> for ConnectError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => Self::Refused,
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Self::Timeout,
+            io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::UnexpectedEof => Self::Closed,
+            kind => Self::Other(kind)
         }
     }
 }
@@ -39,6 +506,7 @@ impl AddConnection {
 pub enum MainElements {
     Connections,
     Console,
+    Players,
     Input,
     Add
 }
@@ -47,7 +515,8 @@ impl MainElements {
     pub fn next(self) -> Self {
         match self {
             Self::Connections => Self::Console,
-            Self::Console => Self::Input,
+            Self::Console => Self::Players,
+            Self::Players => Self::Input,
             Self::Input => Self::Add,
             Self::Add => Self::Connections,
         }
@@ -57,7 +526,8 @@ impl MainElements {
         match self {
             Self::Connections => Self::Add,
             Self::Console => Self::Connections,
-            Self::Input => Self::Console,
+            Self::Players => Self::Console,
+            Self::Input => Self::Players,
             Self::Add => Self::Input,
         }
     }
@@ -68,7 +538,9 @@ pub enum AddConnectionElements {
     Name,
     Address,
     Password,
-    OkButton
+    Group,
+    OkButton,
+    SaveButton
 }
 
 impl AddConnectionElements {
@@ -76,17 +548,21 @@ impl AddConnectionElements {
         match self {
             Self::Name => Self::Address,
             Self::Address => Self::Password,
-            Self::Password => Self::OkButton,
-            Self::OkButton => Self::Name
+            Self::Password => Self::Group,
+            Self::Group => Self::OkButton,
+            Self::OkButton => Self::SaveButton,
+            Self::SaveButton => Self::Name
         }
     }
 
     pub fn prev(&self) -> Self {
         match self {
-            Self::Name => Self::OkButton,
+            Self::Name => Self::SaveButton,
             Self::Address => Self::Name,
             Self::Password => Self::Address,
-            Self::OkButton => Self::Password
+            Self::Group => Self::Password,
+            Self::OkButton => Self::Group,
+            Self::SaveButton => Self::OkButton
         }
     }
 }
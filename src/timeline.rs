@@ -0,0 +1,187 @@
+use std::{collections::VecDeque, iter, time::Instant};
+
+use tui::style::Color;
+
+use crate::{econtab::{self, EconTab}, filter::Filter, parser::Line};
+
+// server labels cycle through these, all readable on the white background
+const LABEL_COLORS: [Color; 6] = [Color::Blue, Color::Magenta, Color::Green, Color::Red, Color::Cyan, Color::DarkGray];
+
+// arrival order, ties broken by connection and then by the line's place in it
+type Key = (Instant, usize, u64);
+
+pub fn label_color(tab: usize) -> Color {
+    LABEL_COLORS[tab % LABEL_COLORS.len()]
+}
+
+// a line of the merged timeline and the connection it came from
+pub struct Entry<'a> {
+    pub tab: usize,
+    pub received: u64,
+    pub line: &'a Line
+}
+
+impl Entry<'_> {
+    fn key(&self) -> Key {
+        (self.line.received_instant, self.tab, self.received)
+    }
+}
+
+// the virtual "All" tab, every connection's scrollback interleaved in arrival order
+pub struct Timeline {
+    pub filter: Filter,
+    // the bottom line while reading older output, None follows the newest one
+    anchor: Option<Key>,
+    // per connection, the `received` numbers of the lines that pass `filter`, oldest first
+    matched: Vec<VecDeque<u64>>,
+    // per connection, the first `received` number `matched` has not looked at yet
+    checked: Vec<u64>
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            filter: Filter::new(),
+            anchor: None,
+            matched: Vec::new(),
+            checked: Vec::new()
+        }
+    }
+
+    // call after changing the filter, every line is looked at again on the next `sync`
+    pub fn refilter(&mut self) {
+        self.filter.update();
+        self.matched.clear();
+        self.checked.clear();
+        self.anchor = None;
+    }
+
+    // filters only the lines that arrived since the last call and forgets the ones the scrollback dropped,
+    // so a narrow filter does not rescan every connection on each frame
+    pub fn sync(&mut self, econ_tabs: &VecDeque<EconTab>) {
+        self.matched.resize_with(econ_tabs.len(), VecDeque::new);
+        self.checked.resize(econ_tabs.len(), 0);
+
+        for (tab, econ_tab) in econ_tabs.iter().enumerate() {
+            let first = econ_tab.first_received();
+            let matched = &mut self.matched[tab];
+
+            for received in self.checked[tab].max(first)..econ_tab.received {
+                if econ_tab.messages.get((received - first) as usize).is_some_and(|line| self.filter.matches(line)) {
+                    matched.push_back(received);
+                }
+            }
+            self.checked[tab] = econ_tab.received;
+
+            while matched.front().is_some_and(|&received| received < first) {
+                matched.pop_front();
+            }
+        }
+    }
+
+    pub fn following(&self) -> bool {
+        self.anchor.is_none()
+    }
+
+    // shown lines older than `from` (itself included) newest first, or newer than it oldest first.
+    // without `from` older ones start at the newest line and newer ones at the oldest. every tab is
+    // already in arrival order, so this only merges their ends and stops as soon as the caller does.
+    // expects `sync` to have caught up with `econ_tabs`
+    fn walk<'a>(&'a self, econ_tabs: &'a VecDeque<EconTab>, from: Option<Key>, older: bool) -> impl Iterator<Item = Entry<'a>> + 'a {
+        let mut cursors: Vec<_> = econ_tabs
+            .iter()
+            .zip(&self.matched)
+            .enumerate()
+            .map(|(tab, (econ_tab, matched))| {
+                let first = econ_tab.first_received();
+                let cut = match from {
+                    Some(from) => cut(econ_tab, matched, tab, from),
+                    None if older => matched.len(),
+                    None => 0
+                };
+                let range = if older { 0..cut } else { cut..matched.len() };
+                let entries = matched
+                    .range(range)
+                    .map(move |&received| Entry { tab, received, line: econ_tab.messages.get((received - first) as usize).unwrap() });
+                let entries: Box<dyn Iterator<Item = Entry<'a>> + 'a> = if older { Box::new(entries.rev()) } else { Box::new(entries) };
+
+                entries.peekable()
+            })
+            .collect();
+
+        iter::from_fn(move || {
+            let (next, _) = cursors
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, cursor)| Some((i, cursor.peek()?.key())))
+                .reduce(|a, b| if (b.1 > a.1) == older { b } else { a })?;
+
+            cursors[next].next()
+        })
+    }
+
+    pub fn scroll_up(&mut self, econ_tabs: &VecDeque<EconTab>, lines: usize) {
+        self.sync(econ_tabs);
+        let top = self.walk(econ_tabs, self.anchor, true).take(lines + 1).last().map(|entry| entry.key());
+
+        if top.is_some() {
+            self.anchor = top;
+        }
+    }
+
+    pub fn scroll_down(&mut self, econ_tabs: &VecDeque<EconTab>, lines: usize) {
+        if self.anchor.is_none() {
+            return;
+        }
+        self.sync(econ_tabs);
+
+        let newer: Vec<Key> = self.walk(econ_tabs, self.anchor, false).take(lines + 1).map(|entry| entry.key()).collect();
+
+        // reaching the newest line follows it again
+        self.anchor = match newer.get(lines.saturating_sub(1)) {
+            Some(key) if newer.len() > lines => Some(*key),
+            _ => None
+        };
+    }
+
+    pub fn scroll_to_top(&mut self, econ_tabs: &VecDeque<EconTab>) {
+        self.sync(econ_tabs);
+        let oldest = self.walk(econ_tabs, None, false).next().map(|entry| entry.key());
+
+        if oldest.is_some() {
+            self.anchor = oldest;
+        }
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.anchor = None;
+    }
+
+    // like EconTab::visible, `text` gives each line as it is drawn. expects `sync` to have caught up
+    pub fn visible<'a>(&'a self, econ_tabs: &'a VecDeque<EconTab>, width: u16, height: u16, text: impl Fn(&Entry) -> String) -> (Vec<Entry<'a>>, u16) {
+        let mut entries = Vec::new();
+        let mut rows = 0;
+
+        for entry in self.walk(econ_tabs, self.anchor, true) {
+            if rows >= height as usize {
+                break;
+            }
+            rows += econtab::wrapped_rows(&text(&entry), width);
+            entries.push(entry);
+        }
+
+        entries.reverse();
+
+        (entries, rows.saturating_sub(height as usize) as u16)
+    }
+}
+
+// how many of the tab's matched lines come at or before `key`
+fn cut(econ_tab: &EconTab, matched: &VecDeque<u64>, tab: usize, key: Key) -> usize {
+    let first = econ_tab.first_received();
+
+    matched.partition_point(|&received| {
+        let line = econ_tab.messages.get((received - first) as usize).unwrap();
+        (line.received_instant, tab, received) <= key
+    })
+}